@@ -0,0 +1,141 @@
+//! Decode a saved Director and print it.
+//!
+//! ```text
+//! inspect <dump>           print the Director in a dump
+//! inspect <before> <after> print what changed between two dumps
+//! ```
+//!
+//! A dump is either the Memory JSON or the output of `dump_director()`.
+//! This only builds with the `native` feature so it stays out of the wasm
+//! build: `cargo run --features native --bin inspect -- <dump>`.
+
+use screeps_rs::*;
+use std::collections::BTreeSet;
+
+fn main() {
+  let paths: Vec<String> = std::env::args().skip(1).collect();
+  let directors = match paths.len() {
+    1 | 2 => paths.iter().map(|path| load(path)).collect::<Vec<_>>(),
+    _ => {
+      eprintln!("usage: inspect <dump> [<other dump>]");
+      std::process::exit(2);
+    }
+  };
+
+  match directors.as_slice() {
+    [director] => {
+      for (section, lines) in summarize(director) {
+        println!("{}", section);
+        for line in lines {
+          println!("  {}", line);
+        }
+      }
+    }
+    [before, after] => diff(&summarize(before), &summarize(after)),
+    _ => unreachable!(),
+  }
+}
+
+/// Read a dump or exit with the reason it couldn't be read
+fn load(path: &str) -> Director {
+  let text = std::fs::read_to_string(path).unwrap_or_else(|e| {
+    eprintln!("unable to read {}: {}", path, e);
+    std::process::exit(1);
+  });
+  read_dump(&text).unwrap_or_else(|e| {
+    eprintln!("{}: {}", path, e);
+    std::process::exit(1);
+  })
+}
+
+/// Describe the Director as lines grouped into sections
+fn summarize(director: &Director) -> BTreeMap<String, Vec<String>> {
+  let mut sections = BTreeMap::new();
+  sections.insert(
+    "Director".to_string(),
+    vec![
+      format!("username {}", director.username()),
+      format!("{} owned rooms", director.owned_rooms().len()),
+      format!("{} scouted rooms", director.scouted_rooms().len()),
+      format!("{} queued tasks", director.task_queue().len()),
+      format!("{} scouted paths", director.scouted_paths().len()),
+    ],
+  );
+
+  for (kind, rooms) in
+    &[("Owned", director.owned_rooms()), ("Scouted", director.scouted_rooms())]
+  {
+    for (name, room) in rooms.iter() {
+      sections.insert(format!("{} room {}", kind, name), room_stats(room));
+    }
+  }
+
+  let tasks = director
+    .task_queue()
+    .iter()
+    .enumerate()
+    .flat_map(|(i, task)| {
+      task
+        .to_string()
+        .lines()
+        .map(|step| format!("{}: {}", i + 1, step))
+        .collect::<Vec<_>>()
+    })
+    .collect();
+  sections.insert("Task queue".to_string(), tasks);
+
+  sections
+}
+
+/// Describe a single room
+fn room_stats(room: &RoomData) -> Vec<String> {
+  let energy: u32 = room.sources().iter().map(|s| s.amount()).sum();
+  let capacity: u32 = room.sources().iter().map(|s| s.capacity()).sum();
+  let structures: Vec<String> = room
+    .structure_counts()
+    .iter()
+    .map(|(kind, count)| format!("{} {}", kind, count))
+    .collect();
+
+  vec![
+    format!("level {}", room.level()),
+    format!(
+      "{} sources with {}/{} energy",
+      room.sources().len(),
+      energy,
+      capacity
+    ),
+    format!("structures: {}", structures.join(", ")),
+    format!("{} construction sites", room.construction().count()),
+    format!("{} creeps", room.my_creeps().len()),
+    format!("{} hostile creeps", room.other_creeps().len()),
+  ]
+}
+
+/// Print the sections and lines that differ between two summaries
+fn diff(
+  before: &BTreeMap<String, Vec<String>>,
+  after: &BTreeMap<String, Vec<String>>,
+) {
+  let sections: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+  let empty = vec![];
+  for section in sections {
+    let old = before.get(section).unwrap_or(&empty);
+    let new = after.get(section).unwrap_or(&empty);
+    if old == new {
+      continue;
+    }
+
+    match (before.contains_key(section), after.contains_key(section)) {
+      (false, _) => println!("+ {}", section),
+      (_, false) => println!("- {}", section),
+      _ => println!("  {}", section),
+    }
+    for line in old.iter().filter(|line| !new.contains(line)) {
+      println!("  - {}", line);
+    }
+    for line in new.iter().filter(|line| !old.contains(line)) {
+      println!("  + {}", line);
+    }
+  }
+}
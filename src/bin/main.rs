@@ -6,6 +6,15 @@ fn main() {
   js! {
       var game_loop = @{game_loop};
 
+      // Record a room as JSON for replaying offline.
+      global.snapshot_room = @{snapshot_room};
+
+      // Print the Director for the inspect tool.
+      global.dump_director = @{dump_director};
+
+      // List the tasks that keep failing and why.
+      global.task_failures = @{task_failures};
+
       module.exports.loop = function() {
           // Provide actual error traces.
           try {
@@ -18,7 +27,8 @@ fn main() {
               }
               console_error("resetting VM next tick.");
               // reset the VM since we don't know if everything was cleaned up and don't
-              // want an inconsistent state.
+              // want an inconsistent state. This also drops the heap Director so
+              // it gets reloaded from the last checkpoint.
               module.exports.loop = wasm_initialize;
           }
       }
@@ -3,32 +3,85 @@ use crate::*;
 /// This is the main game loop that runs the rest of the game.
 /// Try to keep it slim and trim.
 pub fn game_loop() {
-  time_hack("==============loop starting!==============");
+  tick(&mut ScreepsWorld);
+}
 
-  // initialize the director
-  let mut director = Director::default();
+/// Run a single tick against any world
+pub fn tick(world: &mut dyn World) {
+  time_hack(world, "==============loop starting!==============");
 
-  let time = screeps::game::time();
+  let time = world.time();
 
-  // update the director
-  director.update();
+  // the director lives on the heap and is only loaded after a VM reset
+  let result = with_director(world, |director, world| {
+    // the saved rooms are read back a few segments a tick after a VM reset
+    director.read_rooms(world);
 
-  if time % 32 == 3 {
-    info!("running memory cleanup");
-    cleanup_memory()
-      .expect("expected Memory.creeps format to be a regular memory object");
+    // update the director
+    director.update(world);
+
+    let mut creeps = world.my_creeps();
+    let saved: Vec<(Task, Option<u32>)> =
+      creeps.iter().map(|c| (c.task().clone(), c.idle_since())).collect();
+
+    // protect the rooms under attack
+    manage_safe_mode(world, director, &creeps);
+
+    // keep the power creeps alive and busy
+    manage_power_creeps(world, director, &mut creeps);
+
+    // clear up what's been dropped in our rooms
+    request_pickups(director, &creeps);
+
+    // give the queued tasks to idle creeps
+    director.assign_tasks(&mut creeps);
+
+    // spawn creeps for the tasks nobody can take
+    spawn_requested(world, director);
+
+    for creep in &creeps {
+      if let Some(task) = creep.replacement(director) {
+        // the replacement may already be on its way
+        if !creeps.iter().any(|c| c.name() != creep.name() && *c.task() == task)
+        {
+          director.request(task);
+        }
+      }
+    }
+    // run the creeps
+    for (mut creep, (task, idle_since)) in creeps.into_iter().zip(saved) {
+      creep.run(world, director);
+      // only tasks whose steps changed are written back to keep memory writes
+      // down, since executors refresh their targets every tick
+      if !creep.task().same_steps(&task) {
+        world.save_task(creep.name(), creep.task());
+      }
+      if creep.idle_since() != idle_since {
+        world.save_idle(creep.name(), creep.idle_since());
+      }
+    }
+
+    // checkpoint the director
+    if checkpoint(world, director) {
+      trace!("Director successfully saved to memory");
+    }
+  });
+
+  if let Err(e) = result {
+    info!("Waiting to load the Director: {}", e);
+    return;
   }
 
-  // save the director
-  if director.save() {
-    trace!("Director successfully saved to memory");
+  if time % 32 == 3 {
+    info!("running memory cleanup");
+    cleanup_memory(world);
   }
 
-  time_hack("Loop done!");
+  time_hack(world, "Loop done!");
 }
 
 /// This is a quick and easy way to get a time-hack at any point in the program.
-pub fn time_hack(msg: &str) {
-  let _time = game::cpu::get_used();
+pub fn time_hack(world: &dyn World, msg: &str) {
+  let _time = world.cpu_used();
   info!("{} CPU: {}", msg, _time);
 }
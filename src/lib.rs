@@ -47,7 +47,13 @@ pub use init::*;
 pub use memory_data::*;
 pub use memory_manager::*;
 pub use names::*;
+pub use power_creeps::*;
 pub use rooms::*;
+pub use safe_mode::*;
+pub use settings::*;
+pub use sim::*;
+pub use storage::*;
+pub use world::*;
 
 /// access the logging module
 pub mod logging;
@@ -57,4 +63,10 @@ mod init;
 mod memory_data;
 mod memory_manager;
 mod names;
+mod power_creeps;
 mod rooms;
+mod safe_mode;
+mod settings;
+mod sim;
+mod storage;
+mod world;
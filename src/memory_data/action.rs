@@ -1,7 +1,8 @@
 use crate::*;
 
 /// This enumerates all possible actions a creep can take
-#[derive(Serialize, Deserialize, Hash, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Hash, Debug, Eq, PartialEq, Clone)]
+#[serde(remote = "Self")]
 pub enum Action {
   /// Harvest a Resource, Mineral, or Deposit
   /// Requires Work part
@@ -20,9 +21,11 @@ pub enum Action {
   /// Claim a Controller
   /// Requires Claim part
   Claim,
-  /// Dismantle a Structure
+  /// Dismantle a Structure and pick up what it drops.
+  /// Our own spawns, storage and terminals are only dismantled when this is
+  /// set to force it.
   /// Requires Work part
-  Dismantle,
+  Dismantle(bool),
   /// Generate Safe Mode on a Controller
   /// Requires Carry part and 1000 Ghodium
   GenerateSafeMode,
@@ -38,42 +41,156 @@ pub enum Action {
   /// Reserve a Controller
   /// Requires a Claim part
   Reserve,
-  /// Transfer to a Structure
-  /// Requires a Work part as well as a Carry part
-  Transfer,
-  /// Withdraw from a Structure, Tombstone, or Deposit
+  /// Transfer to a Structure or Creep.
+  /// Moves the amount of the resource or all of it when there's no amount.
+  /// Without a resource everything carried is transferred.
   /// Requires a Carry part
-  Withdraw,
+  Transfer(Option<ResourceType>, Option<u32>),
+  /// Withdraw from a Structure, Tombstone, or Ruin.
+  /// Takes the amount of the resource or as much as fits when there's no
+  /// amount. Without a resource anything stored is taken.
+  /// Requires a Carry part
+  Withdraw(Option<ResourceType>, Option<u32>),
   /// Simply scout out a target and add roads
   /// Requires a Move part
   Scout,
   /// Upgrade a Controller
   /// Requires Work, Carry, Move
   Upgrade,
+  /// Renew a power creep at a Power Spawn
+  Renew,
+  /// Enable powers in a room at its Controller
+  EnableRoom,
+  /// Use a power on a target
+  UsePower(PowerType),
+}
+
+crate::layouts!(Action {
+  5 => ActionV4,
+  8 => ActionV7,
+});
+
+/// The layout before transfers and withdrawals named a resource
+#[derive(Deserialize)]
+enum ActionV4 {
+  Harvest,
+  Mine,
+  Attack,
+  AttackController,
+  Build,
+  Claim,
+  Dismantle,
+  GenerateSafeMode,
+  Heal,
+  Pickup,
+  Repair,
+  Reserve,
+  Transfer,
+  Withdraw,
+  Scout,
+  Upgrade,
+}
+
+impl From<ActionV4> for Action {
+  fn from(old: ActionV4) -> Self {
+    match old {
+      ActionV4::Harvest => Action::Harvest,
+      ActionV4::Mine => Action::Mine,
+      ActionV4::Attack => Action::Attack,
+      ActionV4::AttackController => Action::AttackController,
+      ActionV4::Build => Action::Build,
+      ActionV4::Claim => Action::Claim,
+      ActionV4::Dismantle => Action::Dismantle(false),
+      ActionV4::GenerateSafeMode => Action::GenerateSafeMode,
+      ActionV4::Heal => Action::Heal,
+      ActionV4::Pickup => Action::Pickup,
+      ActionV4::Repair => Action::Repair,
+      ActionV4::Reserve => Action::Reserve,
+      // these used to always move energy
+      ActionV4::Transfer => Action::Transfer(Some(Energy), None),
+      ActionV4::Withdraw => Action::Withdraw(Some(Energy), None),
+      ActionV4::Scout => Action::Scout,
+      ActionV4::Upgrade => Action::Upgrade,
+    }
+  }
+}
+
+/// The layout before dismantling could be forced
+#[derive(Deserialize)]
+enum ActionV7 {
+  Harvest,
+  Mine,
+  Attack,
+  AttackController,
+  Build,
+  Claim,
+  Dismantle,
+  GenerateSafeMode,
+  Heal,
+  Pickup,
+  Repair,
+  Reserve,
+  Transfer(Option<ResourceType>, Option<u32>),
+  Withdraw(Option<ResourceType>, Option<u32>),
+  Scout,
+  Upgrade,
+}
+
+impl From<ActionV7> for Action {
+  fn from(old: ActionV7) -> Self {
+    match old {
+      ActionV7::Harvest => Action::Harvest,
+      ActionV7::Mine => Action::Mine,
+      ActionV7::Attack => Action::Attack,
+      ActionV7::AttackController => Action::AttackController,
+      ActionV7::Build => Action::Build,
+      ActionV7::Claim => Action::Claim,
+      ActionV7::Dismantle => Action::Dismantle(false),
+      ActionV7::GenerateSafeMode => Action::GenerateSafeMode,
+      ActionV7::Heal => Action::Heal,
+      ActionV7::Pickup => Action::Pickup,
+      ActionV7::Repair => Action::Repair,
+      ActionV7::Reserve => Action::Reserve,
+      ActionV7::Transfer(r, amount) => Action::Transfer(r, amount),
+      ActionV7::Withdraw(r, amount) => Action::Withdraw(r, amount),
+      ActionV7::Scout => Action::Scout,
+      ActionV7::Upgrade => Action::Upgrade,
+    }
+  }
 }
 
 impl Action {
-  /// Get the required body parts for an action
-  pub fn req_parts(&self) -> HashSet<Part> {
-    match self {
+  /// Get the required body parts for an action.
+  /// A creep needs at least one part from each group.
+  pub fn req_parts(&self) -> Vec<HashSet<Part>> {
+    let all = match self {
+      // either kind of attack will do
+      Action::Attack => {
+        return vec![any(&[Attack, RangedAttack]), any(&[Move])]
+      }
       Action::Harvest => vec![Work, Carry, Move],
       Action::Mine => vec![Work, Carry, Move],
-      Action::Attack => vec![Attack, RangedAttack, Move],
       Action::AttackController => vec![Claim, Move],
       Action::Build => vec![Carry, Work, Move],
       Action::Claim => vec![Claim, Move],
-      Action::Dismantle => vec![Work, Move],
+      Action::Dismantle(_) => vec![Work, Move],
       Action::GenerateSafeMode => vec![Carry, Move],
       Action::Heal => vec![Heal, Move],
       Action::Pickup => vec![Carry, Move],
       Action::Repair => vec![Work, Carry, Move],
       Action::Reserve => vec![Claim, Move],
-      Action::Transfer => vec![Carry, Move],
-      Action::Withdraw => vec![Carry, Move],
+      Action::Transfer(..) => vec![Carry, Move],
+      Action::Withdraw(..) => vec![Carry, Move],
       Action::Scout => vec![Move],
       Action::Upgrade => vec![Work, Carry, Move],
-    }
-    .into_iter()
-    .collect()
+      // only power creeps can do these and they have no body
+      Action::Renew | Action::EnableRoom | Action::UsePower(_) => vec![],
+    };
+    all.iter().map(|p| any(&[*p])).collect()
   }
 }
+
+/// A group of parts where any one of them will do
+fn any(parts: &[Part]) -> HashSet<Part> {
+  parts.iter().cloned().collect()
+}
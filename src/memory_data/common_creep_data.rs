@@ -1,7 +1,8 @@
 use crate::*;
 
 /// This serializes and wraps creeps
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+#[serde(remote = "Self")]
 pub struct CommonCreepData {
   pos: CommonData,
   name: String,
@@ -10,6 +11,44 @@ pub struct CommonCreepData {
   max_hits: u32,
   parts: HashSet<Part>,
   store: HashMap<ResourceType, u32>,
+  capacity: u32,
+  ticks_to_live: u32,
+  active_parts: HashMap<Part, u32>,
+}
+
+crate::layouts!(CommonCreepData {
+  2 => CommonCreepDataV1,
+});
+
+/// The layout before capacity, ticks to live and active parts were tracked
+#[derive(Deserialize)]
+struct CommonCreepDataV1 {
+  pos: CommonData,
+  name: String,
+  id: ObjectId<Creep>,
+  hits: u32,
+  max_hits: u32,
+  parts: HashSet<Part>,
+  store: HashMap<ResourceType, u32>,
+}
+
+impl From<CommonCreepDataV1> for CommonCreepData {
+  fn from(old: CommonCreepDataV1) -> Self {
+    // These get filled in the next time the creep is seen.
+    let active_parts = old.parts.iter().map(|p| (*p, 1)).collect();
+    CommonCreepData {
+      pos: old.pos,
+      name: old.name,
+      id: old.id,
+      hits: old.hits,
+      max_hits: old.max_hits,
+      parts: old.parts,
+      store: old.store,
+      capacity: 0,
+      ticks_to_live: 0,
+      active_parts,
+    }
+  }
 }
 
 impl HasPosition for CommonCreepData {
@@ -50,16 +89,33 @@ impl From<Creep> for CommonCreepData {
     let max_hits = c.hits_max();
     let mut store = HashMap::<ResourceType, u32>::new();
     let mut parts = HashSet::<Part>::new();
+    let mut active_parts = HashMap::<Part, u32>::new();
+    let capacity = c.store_capacity(None);
+    let ticks_to_live = c.ticks_to_live().unwrap_or(CREEP_LIFE_TIME);
 
     for part in c.body() {
       parts.insert(part.part);
+      if part.hits > 0 {
+        *active_parts.entry(part.part).or_default() += 1;
+      }
     }
 
     for r in c.store_types() {
       store.insert(r, c.store_of(r));
     }
 
-    CommonCreepData { pos, name, id, hits, max_hits, store, parts }
+    CommonCreepData {
+      pos,
+      name,
+      id,
+      hits,
+      max_hits,
+      store,
+      parts,
+      capacity,
+      ticks_to_live,
+      active_parts,
+    }
   }
 }
 
@@ -72,12 +128,26 @@ impl From<PowerCreep> for CommonCreepData {
     let max_hits = c.hits_max();
     let mut store = HashMap::<ResourceType, u32>::new();
     let parts = HashSet::<Part>::new();
+    let active_parts = HashMap::<Part, u32>::new();
+    let capacity = c.store_capacity(None);
+    let ticks_to_live = c.ticks_to_live().unwrap_or(POWER_CREEP_LIFE_TIME);
 
     for r in c.store_types() {
       store.insert(r, c.store_of(r));
     }
 
-    CommonCreepData { pos, name, id, hits, max_hits, store, parts }
+    CommonCreepData {
+      pos,
+      name,
+      id,
+      hits,
+      max_hits,
+      store,
+      parts,
+      capacity,
+      ticks_to_live,
+      active_parts,
+    }
   }
 }
 
@@ -112,145 +182,74 @@ impl CommonCreepData {
     }
   }
 
+  /// The number of active parts of a type
+  pub fn active_parts(&self, part: Part) -> u32 {
+    self.active_parts.get(&part).cloned().unwrap_or(0)
+  }
+
   /// Get Harvesting Power of this creep
   pub fn harvesting_power(&self) -> u32 {
-    if self.is_power_creep() {
-      return 0;
-    }
-
-    if let Some(creep) = self.creep() {
-      let parts = creep.get_active_bodyparts(Work);
-      return parts * HARVEST_POWER;
-    }
-
-    error!("Creep has an invalid id!");
-    0
+    self.active_parts(Work) * HARVEST_POWER
   }
 
   /// Get Harvest Mineral Power
   pub fn harvest_mineral_power(&self) -> u32 {
-    if self.is_power_creep() {
-      return 0;
-    }
-
-    if let Some(creep) = self.creep() {
-      let parts = creep.get_active_bodyparts(Work);
-      return parts * HARVEST_MINERAL_POWER;
-    }
-
-    error!("Creep has an invalid id!");
-    0
+    self.active_parts(Work) * HARVEST_MINERAL_POWER
   }
 
   /// Get Build Power
   pub fn build_power(&self) -> u32 {
-    if self.is_power_creep() {
-      return 0;
-    }
-
-    if let Some(creep) = self.creep() {
-      let parts = creep.get_active_bodyparts(Work);
-      return parts * BUILD_POWER;
-    }
-
-    error!("Creep has an invalid id!");
-    0
+    self.active_parts(Work) * BUILD_POWER
   }
 
   /// Get the attack power
   pub fn attack_power(&self) -> u32 {
-    if let Some(creep) = self.creep() {
-      let parts = creep.get_active_bodyparts(Attack);
-
-      return parts * ATTACK_POWER;
-    }
-
-    error!("Creep has an invalid id!");
-    0
+    self.active_parts(Attack) * ATTACK_POWER
   }
 
   /// Get ranged attack power
   pub fn ranged_attack_power(&self) -> u32 {
-    if let Some(creep) = self.creep() {
-      let parts = creep.get_active_bodyparts(RangedAttack);
+    self.active_parts(RangedAttack) * RANGED_ATTACK_POWER
+  }
 
-      return parts * RANGED_ATTACK_POWER;
+  /// Get the damage a ranged mass attack does to a target this far away
+  pub fn mass_attack_power(&self, range: u32) -> u32 {
+    match range {
+      0 | 1 => self.ranged_attack_power(),
+      2 => self.ranged_attack_power() * 4 / 10,
+      3 => self.ranged_attack_power() / 10,
+      _ => 0,
     }
-
-    error!("Creep has an invalid id!");
-    0
   }
 
   /// Get carry capacity
   pub fn carry_capacity(&self) -> u32 {
-    if let Some(creep) = self.creep() {
-      let parts = creep.get_active_bodyparts(Carry);
-
-      return parts * CARRY_CAPACITY;
-    }
-
-    error!("Creep has an invalid id!");
-    0
+    self.active_parts(Carry) * CARRY_CAPACITY
   }
 
   /// Get dismantle power
   pub fn dismantle_power(&self) -> u32 {
-    if let Some(creep) = self.creep() {
-      let parts = creep.get_active_bodyparts(Work);
-
-      return parts * DISMANTLE_POWER;
-    }
-
-    error!("Creep has an invalid id!");
-    0
+    self.active_parts(Work) * DISMANTLE_POWER
   }
 
   /// Get heal power
   pub fn heal_power(&self) -> u32 {
-    if let Some(creep) = self.creep() {
-      let parts = creep.get_active_bodyparts(Heal);
-
-      return parts * HEAL_POWER;
-    }
-
-    error!("Creep has an invalid id!");
-    0
+    self.active_parts(Heal) * HEAL_POWER
   }
 
   /// Get Ranged Heal Power
   pub fn ranged_heal_power(&self) -> u32 {
-    if let Some(creep) = self.creep() {
-      let parts = creep.get_active_bodyparts(Heal);
-
-      return parts * RANGED_HEAL_POWER;
-    }
-
-    error!("Creep has an invalid id!");
-    0
+    self.active_parts(Heal) * RANGED_HEAL_POWER
   }
 
   /// Repair power
   pub fn repair_power(&self) -> u32 {
-    if let Some(creep) = self.creep() {
-      let parts = creep.get_active_bodyparts(Work);
-
-      return parts * REPAIR_POWER;
-    }
-
-    error!("Creep has an invalid id!");
-    0
+    self.active_parts(Work) * REPAIR_POWER
   }
 
   /// Upgrade power
   pub fn upgrade_power(&self) -> u32 {
-    if let Some(creep) = self.creep() {
-      let parts = creep.get_active_bodyparts(Work);
-
-      return parts * UPGRADE_CONTROLLER_POWER;
-    }
-
-    error!("Creep has an invalid id!");
-    0
+    self.active_parts(Work) * UPGRADE_CONTROLLER_POWER
   }
 }
 
@@ -267,8 +266,18 @@ impl CommonCreepData {
 
     // update hits
     self.hits = creep.hits();
+    self.ticks_to_live = creep.ticks_to_live().unwrap_or(CREEP_LIFE_TIME);
+
+    // update active parts
+    self.active_parts.clear();
+    for part in creep.body() {
+      if part.hits > 0 {
+        *self.active_parts.entry(part.part).or_default() += 1;
+      }
+    }
 
     // update store
+    self.store.clear();
     for r in creep.store_types() {
       self.store.insert(r, creep.store_of(r));
     }
@@ -283,4 +292,111 @@ impl CommonCreepData {
   pub fn name(&self) -> &str {
     &self.name
   }
+
+  /// Get the creeps id
+  pub fn id(&self) -> ObjectId<Creep> {
+    self.id
+  }
+
+  /// Get the current hits
+  pub fn hits(&self) -> u32 {
+    self.hits
+  }
+
+  /// Get the max hits
+  pub fn max_hits(&self) -> u32 {
+    self.max_hits
+  }
+
+  /// Get the number of ticks this creep has left
+  pub fn ticks_to_live(&self) -> u32 {
+    self.ticks_to_live
+  }
+
+  /// Get the amount of a resource being carried
+  pub fn store_of(&self, r: ResourceType) -> u32 {
+    self.store.get(&r).cloned().unwrap_or(0)
+  }
+
+  /// Get everything being carried
+  pub fn store(&self) -> &HashMap<ResourceType, u32> {
+    &self.store
+  }
+
+  /// Pick something being carried, preferring energy
+  pub fn carried(&self) -> Option<ResourceType> {
+    first_resource(&self.store)
+  }
+
+  /// Get the most this creep can carry
+  pub fn capacity(&self) -> u32 {
+    self.capacity
+  }
+
+  /// Get the number of body parts that still work
+  pub fn body_size(&self) -> u32 {
+    self.active_parts.values().sum()
+  }
+
+  /// Get the total amount being carried
+  pub fn used_capacity(&self) -> u32 {
+    self.store.values().sum()
+  }
+
+  /// Get the room left to carry more
+  pub fn free_capacity(&self) -> u32 {
+    self.capacity.saturating_sub(self.used_capacity())
+  }
+}
+
+/// Creeps for the mock world. The simulator moves them and changes what they
+/// carry through these.
+#[cfg(any(test, feature = "native"))]
+impl CommonCreepData {
+  /// Build creep data
+  pub fn new(
+    name: &str,
+    id: ObjectId<Creep>,
+    pos: Position,
+    body: &[Part],
+  ) -> Self {
+    let mut active_parts = HashMap::<Part, u32>::new();
+    for part in body {
+      *active_parts.entry(*part).or_default() += 1;
+    }
+    let capacity =
+      active_parts.get(&Carry).cloned().unwrap_or(0) * CARRY_CAPACITY;
+
+    CommonCreepData {
+      pos: pos.into(),
+      name: name.to_string(),
+      id,
+      hits: body.len() as u32 * 100,
+      max_hits: body.len() as u32 * 100,
+      parts: body.iter().cloned().collect(),
+      store: HashMap::new(),
+      capacity,
+      ticks_to_live: CREEP_LIFE_TIME,
+      active_parts,
+    }
+  }
+
+  /// Move this creep
+  pub fn set_pos(&mut self, pos: Position) {
+    self.pos = pos.into();
+  }
+
+  /// Change how long this creep has left to live
+  pub fn set_ticks_to_live(&mut self, ticks: u32) {
+    self.ticks_to_live = ticks;
+  }
+
+  /// Change how much of a resource is carried
+  pub fn set_store(&mut self, r: ResourceType, amount: u32) {
+    if amount == 0 {
+      self.store.remove(&r);
+    } else {
+      self.store.insert(r, amount);
+    }
+  }
 }
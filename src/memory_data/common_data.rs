@@ -4,7 +4,7 @@ use screeps::Terrain;
 use std::ops::Deref;
 
 /// This holds data common to all room objects and generates terrain data to go with it.
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 pub struct CommonData {
   pos: Position,
   scouted: HashSet<Position>,
@@ -56,7 +56,9 @@ impl IntoIterator for CommonDataVec {
     let mut source = self.0;
     while !source.is_empty() {
       let next = source.pop().unwrap().pos;
-      vec.push((next, std::u32::MAX));
+      // getting next to a goal is close enough and works for goals that
+      // can't be stood on
+      vec.push((next, 1));
     }
     vec.into_iter()
   }
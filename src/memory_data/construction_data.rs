@@ -1,7 +1,7 @@
 use crate::*;
 
 /// Holds all essential data for construction
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 pub struct ConstructionData {
   pos: CommonData,
   id: ObjectId<ConstructionSite>,
@@ -42,3 +42,45 @@ impl From<ConstructionSite> for ConstructionData {
     ConstructionData { pos, id, progress, progress_total, structure_type }
   }
 }
+
+impl ConstructionData {
+  /// Get the id of the construction site
+  pub fn id(&self) -> ObjectId<ConstructionSite> {
+    self.id
+  }
+
+  /// Get the type of structure being built
+  pub fn structure_type(&self) -> StructureType {
+    self.structure_type
+  }
+
+  /// Get the progress made so far
+  pub fn progress(&self) -> u32 {
+    self.progress
+  }
+
+  /// Get the progress needed to finish
+  pub fn progress_total(&self) -> u32 {
+    self.progress_total
+  }
+}
+
+/// Construction sites placed by fixtures and built up by the simulator
+#[cfg(any(test, feature = "native"))]
+impl ConstructionData {
+  /// Build construction data
+  pub fn new(
+    pos: Position,
+    id: ObjectId<ConstructionSite>,
+    structure_type: StructureType,
+    progress_total: u32,
+  ) -> Self {
+    let pos = pos.into();
+    ConstructionData { pos, id, progress: 0, progress_total, structure_type }
+  }
+
+  /// Change the progress made so far
+  pub fn set_progress(&mut self, progress: u32) {
+    self.progress = progress.min(self.progress_total);
+  }
+}
@@ -0,0 +1,334 @@
+use crate::*;
+
+/// Holds everything needed to upgrade, claim or reserve a controller
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+#[serde(remote = "Self")]
+pub struct ControllerData {
+  pos: CommonData,
+  id: ObjectId<StructureController>,
+  my: bool,
+  level: u8,
+  progress: u32,
+  progress_total: u32,
+  ticks_to_downgrade: u32,
+  upgrade_blocked: u32,
+  owner: Option<String>,
+  reserved_by: Option<String>,
+  /// The tick the reservation runs out on so it stays meaningful when the
+  /// room can't be seen
+  reserved_until: u32,
+  /// The ticks left on an active safe mode
+  safe_mode: u32,
+  /// The safe modes that can be activated
+  safe_mode_available: u32,
+  /// The ticks before another safe mode can be activated
+  safe_mode_cooldown: u32,
+  /// Set once a power creep has enabled powers in the room
+  power_enabled: bool,
+}
+
+crate::layouts!(ControllerData {
+  7 => ControllerDataV6,
+  10 => ControllerDataV9,
+  11 => ControllerDataV10,
+});
+
+/// The layout before owners and reservations were tracked
+#[derive(Deserialize)]
+struct ControllerDataV6 {
+  pos: CommonData,
+  id: ObjectId<StructureController>,
+  my: bool,
+  level: u8,
+  progress: u32,
+  progress_total: u32,
+  ticks_to_downgrade: u32,
+  upgrade_blocked: u32,
+}
+
+impl From<ControllerDataV6> for ControllerData {
+  fn from(old: ControllerDataV6) -> Self {
+    // These get filled in the next time the room is seen.
+    ControllerData {
+      pos: old.pos,
+      id: old.id,
+      my: old.my,
+      level: old.level,
+      progress: old.progress,
+      progress_total: old.progress_total,
+      ticks_to_downgrade: old.ticks_to_downgrade,
+      upgrade_blocked: old.upgrade_blocked,
+      owner: None,
+      reserved_by: None,
+      reserved_until: 0,
+      safe_mode: 0,
+      safe_mode_available: 0,
+      safe_mode_cooldown: 0,
+      power_enabled: false,
+    }
+  }
+}
+
+/// The layout before safe mode was tracked
+#[derive(Deserialize)]
+struct ControllerDataV9 {
+  pos: CommonData,
+  id: ObjectId<StructureController>,
+  my: bool,
+  level: u8,
+  progress: u32,
+  progress_total: u32,
+  ticks_to_downgrade: u32,
+  upgrade_blocked: u32,
+  owner: Option<String>,
+  reserved_by: Option<String>,
+  reserved_until: u32,
+}
+
+impl From<ControllerDataV9> for ControllerData {
+  fn from(old: ControllerDataV9) -> Self {
+    // These get filled in the next time the room is seen.
+    ControllerData {
+      pos: old.pos,
+      id: old.id,
+      my: old.my,
+      level: old.level,
+      progress: old.progress,
+      progress_total: old.progress_total,
+      ticks_to_downgrade: old.ticks_to_downgrade,
+      upgrade_blocked: old.upgrade_blocked,
+      owner: old.owner,
+      reserved_by: old.reserved_by,
+      reserved_until: old.reserved_until,
+      safe_mode: 0,
+      safe_mode_available: 0,
+      safe_mode_cooldown: 0,
+      power_enabled: false,
+    }
+  }
+}
+
+/// The layout before power was tracked
+#[derive(Deserialize)]
+struct ControllerDataV10 {
+  pos: CommonData,
+  id: ObjectId<StructureController>,
+  my: bool,
+  level: u8,
+  progress: u32,
+  progress_total: u32,
+  ticks_to_downgrade: u32,
+  upgrade_blocked: u32,
+  owner: Option<String>,
+  reserved_by: Option<String>,
+  reserved_until: u32,
+  safe_mode: u32,
+  safe_mode_available: u32,
+  safe_mode_cooldown: u32,
+}
+
+impl From<ControllerDataV10> for ControllerData {
+  fn from(old: ControllerDataV10) -> Self {
+    // This gets filled in the next time the room is seen.
+    ControllerData {
+      pos: old.pos,
+      id: old.id,
+      my: old.my,
+      level: old.level,
+      progress: old.progress,
+      progress_total: old.progress_total,
+      ticks_to_downgrade: old.ticks_to_downgrade,
+      upgrade_blocked: old.upgrade_blocked,
+      owner: old.owner,
+      reserved_by: old.reserved_by,
+      reserved_until: old.reserved_until,
+      safe_mode: old.safe_mode,
+      safe_mode_available: old.safe_mode_available,
+      safe_mode_cooldown: old.safe_mode_cooldown,
+      power_enabled: false,
+    }
+  }
+}
+
+impl HasPosition for ControllerData {
+  fn pos(&self) -> Position {
+    self.pos.pos()
+  }
+}
+
+impl Display for ControllerData {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    writeln!(f, "Controller {}: level {}", self.pos, self.level)?;
+    writeln!(f, "{} of {} progress", self.progress, self.progress_total)?;
+    writeln!(f, "downgrades in {} ticks", self.ticks_to_downgrade)
+  }
+}
+
+impl From<StructureController> for ControllerData {
+  fn from(ctrl: StructureController) -> Self {
+    let reservation = ctrl.reservation();
+    ControllerData {
+      owner: ctrl.owner_name(),
+      reserved_by: reservation.as_ref().map(|r| r.username.clone()),
+      reserved_until: reservation
+        .map(|r| game::time() + r.ticks_to_end)
+        .unwrap_or(0),
+      pos: ctrl.pos().into(),
+      id: ctrl.id(),
+      my: ctrl.my(),
+      level: ctrl.level() as u8,
+      progress: ctrl.progress().unwrap_or(0),
+      progress_total: ctrl.progress_total().unwrap_or(0),
+      ticks_to_downgrade: ctrl.ticks_to_downgrade(),
+      upgrade_blocked: ctrl.upgrade_blocked().unwrap_or(0),
+      safe_mode: ctrl.safe_mode().unwrap_or(0),
+      safe_mode_available: ctrl.safe_mode_available(),
+      safe_mode_cooldown: ctrl.safe_mode_cooldown().unwrap_or(0),
+      power_enabled: ctrl.is_power_enabled(),
+    }
+  }
+}
+
+impl ControllerData {
+  /// Get the id of the controller
+  pub fn id(&self) -> ObjectId<StructureController> {
+    self.id
+  }
+
+  /// Returns true if we own the controller
+  pub fn my(&self) -> bool {
+    self.my
+  }
+
+  /// Get the controller level
+  pub fn level(&self) -> u8 {
+    self.level
+  }
+
+  /// Get the progress toward the next level
+  pub fn progress(&self) -> u32 {
+    self.progress
+  }
+
+  /// Get the progress needed for the next level.
+  /// This is 0 at the max level.
+  pub fn progress_total(&self) -> u32 {
+    self.progress_total
+  }
+
+  /// Get the ticks left before the controller loses a level
+  pub fn ticks_to_downgrade(&self) -> u32 {
+    self.ticks_to_downgrade
+  }
+
+  /// Get the ticks left before the controller can be upgraded again
+  pub fn upgrade_blocked(&self) -> u32 {
+    self.upgrade_blocked
+  }
+
+  /// Get the name of the player that owns the controller
+  pub fn owner(&self) -> Option<&str> {
+    self.owner.as_deref()
+  }
+
+  /// Get the name of the player with a reservation on the controller
+  pub fn reserved_by(&self) -> Option<&str> {
+    self.reserved_by.as_deref()
+  }
+
+  /// Get the ticks left on the reservation as of `time`
+  pub fn reservation_ticks(&self, time: u32) -> u32 {
+    match self.reserved_by {
+      Some(_) => self.reserved_until.saturating_sub(time),
+      None => 0,
+    }
+  }
+
+  /// Get the ticks left on an active safe mode
+  pub fn safe_mode(&self) -> u32 {
+    self.safe_mode
+  }
+
+  /// Get the number of safe modes that can be activated
+  pub fn safe_mode_available(&self) -> u32 {
+    self.safe_mode_available
+  }
+
+  /// Get the ticks before another safe mode can be activated
+  pub fn safe_mode_cooldown(&self) -> u32 {
+    self.safe_mode_cooldown
+  }
+
+  /// Returns true if safe mode could be activated on this controller this
+  /// tick, ignoring safe modes active in other rooms
+  pub fn can_activate_safe_mode(&self) -> bool {
+    self.my
+      && self.safe_mode == 0
+      && self.safe_mode_cooldown == 0
+      && self.safe_mode_available > 0
+  }
+
+  /// Returns true if powers can be used in the room
+  pub fn power_enabled(&self) -> bool {
+    self.power_enabled
+  }
+
+  /// Returns true if the controller can be upgraded this tick
+  pub fn can_upgrade(&self) -> bool {
+    self.my && self.upgrade_blocked == 0
+  }
+}
+
+/// Lets fixtures and tests set up a controller at any level
+#[cfg(any(test, feature = "native"))]
+impl ControllerData {
+  /// Build controller data
+  pub fn new(
+    pos: Position,
+    id: ObjectId<StructureController>,
+    my: bool,
+    level: u8,
+  ) -> Self {
+    ControllerData {
+      pos: pos.into(),
+      id,
+      my,
+      level,
+      progress: 0,
+      progress_total: 0,
+      ticks_to_downgrade: 0,
+      upgrade_blocked: 0,
+      owner: None,
+      reserved_by: None,
+      reserved_until: 0,
+      safe_mode: 0,
+      safe_mode_available: 0,
+      safe_mode_cooldown: 0,
+      power_enabled: false,
+    }
+  }
+
+  /// Change the level and progress
+  pub fn set_progress(
+    &mut self,
+    level: u8,
+    progress: u32,
+    progress_total: u32,
+  ) {
+    self.level = level;
+    self.progress = progress;
+    self.progress_total = progress_total;
+  }
+
+  /// Change the safe mode state
+  pub fn set_safe_mode(&mut self, active: u32, available: u32, cooldown: u32) {
+    self.safe_mode = active;
+    self.safe_mode_available = available;
+    self.safe_mode_cooldown = cooldown;
+  }
+
+  /// Enable powers in the room
+  pub fn set_power_enabled(&mut self, enabled: bool) {
+    self.power_enabled = enabled;
+  }
+}
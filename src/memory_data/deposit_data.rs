@@ -1,7 +1,7 @@
 use crate::*;
 
 /// This serializes deposit data
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 pub struct DepositData {
   pos: CommonData,
   id: ObjectId<Deposit>,
@@ -43,6 +43,11 @@ impl From<Deposit> for DepositData {
 }
 
 impl DepositData {
+  /// Get the id of the deposit
+  pub fn id(&self) -> ObjectId<Deposit> {
+    self.id
+  }
+
   /// unwrap the deposit
   pub fn unwrap(&self) -> Deposit {
     game::get_object_typed(self.id).unwrap().unwrap()
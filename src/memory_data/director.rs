@@ -1,20 +1,131 @@
 use crate::*;
 
 /// The Director is a master wrapper that holds all global data and manages all creeps,
-/// rooms etc. It will get saved/loaded to/from memory each tick.
+/// rooms etc. It lives on the heap between ticks and is checkpointed to the
+/// RawMemory segments every few ticks.
 /// It will also Own everything!
 #[derive(Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct Director {
   /// The player's username
   username: String,
-  /// A collection of each cell that is owned by us
+  /// A collection of each cell that is owned by us.
+  /// Rooms are saved in their own Memory entries.
+  #[serde(skip)]
   owned_rooms: HashMap<RoomName, RoomData>,
   /// A collection of each cell that has been scouted
+  #[serde(skip)]
   scouted_rooms: HashMap<RoomName, RoomData>,
   task_queue: VecDeque<Task>,
+  /// The tiles scouts walked between two points, for planning roads
+  scouted_paths: HashMap<(Position, Position), Vec<Position>>,
+  /// How each task that keeps failing has gone, keyed by its last step
+  failures: HashMap<(Action, Position), TaskFailure>,
+  /// The creeps queued tasks are waiting on, rebuilt every assignment pass
+  #[serde(skip)]
+  spawn_requests: Vec<SpawnRequest>,
+  /// Tracks which rooms need to be serialized again
+  #[serde(skip)]
+  room_cache: RoomCache,
 }
 
-const DIRECTOR_KEY: &str = "Director";
+crate::layouts!(Director {
+  9 => DirectorV8,
+  12 => DirectorV11,
+  13 => DirectorV12,
+});
+
+/// The layout before scouted paths were kept
+#[derive(Deserialize)]
+struct DirectorV8 {
+  username: String,
+  owned_rooms: HashMap<RoomName, RoomData>,
+  scouted_rooms: HashMap<RoomName, RoomData>,
+  task_queue: VecDeque<Task>,
+}
+
+impl From<DirectorV8> for Director {
+  fn from(old: DirectorV8) -> Self {
+    Director {
+      username: old.username,
+      owned_rooms: old.owned_rooms,
+      scouted_rooms: old.scouted_rooms,
+      task_queue: old.task_queue,
+      scouted_paths: HashMap::new(),
+      failures: HashMap::new(),
+      spawn_requests: Vec::new(),
+      room_cache: RoomCache::default(),
+    }
+  }
+}
+
+/// The layout before rooms were saved in their own Memory entries
+#[derive(Deserialize)]
+struct DirectorV11 {
+  username: String,
+  owned_rooms: HashMap<RoomName, RoomData>,
+  scouted_rooms: HashMap<RoomName, RoomData>,
+  task_queue: VecDeque<Task>,
+  scouted_paths: HashMap<(Position, Position), Vec<Position>>,
+}
+
+impl From<DirectorV11> for Director {
+  fn from(old: DirectorV11) -> Self {
+    Director {
+      username: old.username,
+      owned_rooms: old.owned_rooms,
+      scouted_rooms: old.scouted_rooms,
+      task_queue: old.task_queue,
+      scouted_paths: old.scouted_paths,
+      failures: HashMap::new(),
+      spawn_requests: Vec::new(),
+      room_cache: RoomCache::default(),
+    }
+  }
+}
+
+/// The layout before task failures were saved
+#[derive(Deserialize)]
+struct DirectorV12 {
+  username: String,
+  task_queue: VecDeque<Task>,
+  scouted_paths: HashMap<(Position, Position), Vec<Position>>,
+}
+
+impl From<DirectorV12> for Director {
+  fn from(old: DirectorV12) -> Self {
+    Director {
+      username: old.username,
+      owned_rooms: HashMap::new(),
+      scouted_rooms: HashMap::new(),
+      task_queue: old.task_queue,
+      scouted_paths: old.scouted_paths,
+      failures: HashMap::new(),
+      spawn_requests: Vec::new(),
+      room_cache: RoomCache::default(),
+    }
+  }
+}
+
+/// How much a creep can carry for tasks that move resources, otherwise how
+/// many of the parts doing the work it has
+fn strength(creep: &MyCreepData, parts: &[HashSet<Part>]) -> u32 {
+  let parts: HashSet<Part> = parts.iter().flatten().cloned().collect();
+  if parts.contains(&Part::Carry) {
+    creep.capacity()
+  } else {
+    parts.iter().map(|p| creep.active_parts(*p)).sum()
+  }
+}
+
+/// What a task is known by while it fails, as its steps are used up from the
+/// front
+fn failure_key(task: &Task) -> Option<(Action, Position)> {
+  task.back().map(|(action, target)| (action.clone(), target.pos()))
+}
+
+/// Where the Director was saved before it moved into RawMemory segments
+pub const DIRECTOR_KEY: &str = "Director";
 
 impl Display for Director {
   fn fmt(&self, f: &mut Formatter<'_>) -> Result {
@@ -32,63 +143,634 @@ impl Display for Director {
   }
 }
 
-impl Default for Director {
-  fn default() -> Self {
-    // initialize from current data or memory
-    if let Ok(Some(data)) = root().arr(DIRECTOR_KEY) {
-      if let Ok(data) = deserialize(&data) {
-        trace!("\nDirector successfully deserialize from memory: {}", &data);
-        return data;
+impl Director {
+  /// Build a fresh director from everything currently visible
+  pub fn new(world: &dyn World) -> Self {
+    // This should never panic as we always have at least 1 spawn in the game
+    let username = world.username().unwrap();
+    let mut director = Director {
+      username,
+      owned_rooms: HashMap::new(),
+      scouted_rooms: HashMap::new(),
+      task_queue: VecDeque::new(),
+      scouted_paths: HashMap::new(),
+      failures: HashMap::new(),
+      spawn_requests: Vec::new(),
+      room_cache: RoomCache::default(),
+    };
+    director.update(world);
+    director
+  }
+
+  /// Update the director
+  pub fn update(&mut self, world: &dyn World) {
+    for name in world.room_names() {
+      self.observe(world, name);
+    }
+  }
+
+  /// Take a snapshot of a single room if it's visible
+  pub fn observe(&mut self, world: &dyn World, name: RoomName) {
+    let room = match world.room(name) {
+      Some(room) => room,
+      None => return,
+    };
+    if world.is_mine(name) {
+      self.room_cache.track(
+        &mut self.owned_rooms,
+        &mut self.scouted_rooms,
+        name,
+        room,
+      );
+    } else {
+      self.room_cache.track(
+        &mut self.scouted_rooms,
+        &mut self.owned_rooms,
+        name,
+        room,
+      );
+    }
+  }
+
+  /// Get the name of the player this Director works for
+  pub fn username(&self) -> &str {
+    &self.username
+  }
+
+  /// Get the rooms we own
+  pub fn owned_rooms(&self) -> &HashMap<RoomName, RoomData> {
+    &self.owned_rooms
+  }
+
+  /// Get the rooms we have scouted
+  pub fn scouted_rooms(&self) -> &HashMap<RoomName, RoomData> {
+    &self.scouted_rooms
+  }
+
+  /// Get a room we own or have scouted, as of the last time it was seen
+  pub fn room(&self, name: RoomName) -> Option<&RoomData> {
+    self.owned_rooms.get(&name).or_else(|| self.scouted_rooms.get(&name))
+  }
+
+  /// Get the tasks waiting for a creep
+  pub fn task_queue(&self) -> &VecDeque<Task> {
+    &self.task_queue
+  }
+
+  /// Get the tiles scouts walked, keyed by where they started and where they
+  /// were headed
+  pub fn scouted_paths(&self) -> &HashMap<(Position, Position), Vec<Position>> {
+    &self.scouted_paths
+  }
+
+  /// Remember the tiles a scout walked between two points
+  pub fn record_path(
+    &mut self,
+    from: Position,
+    to: Position,
+    tiles: Vec<Position>,
+  ) {
+    self.scouted_paths.insert((from, to), tiles);
+  }
+
+  /// Queue a task for a creep unless it's already waiting
+  pub fn request(&mut self, task: Task) {
+    if !self.task_queue.contains(&task) {
+      self.task_queue.push_back(task);
+    }
+  }
+
+  /// Take back what's left of a task a creep gave up on and queue it again
+  /// for another creep. A task is dropped once it has failed `task_attempts`
+  /// times, so whoever asked for it can ask again later.
+  pub fn task_failed(
+    &mut self,
+    world: &dyn World,
+    task: Task,
+    creep: &str,
+    reason: String,
+  ) {
+    let key = match failure_key(&task) {
+      Some(key) => key,
+      None => return,
+    };
+    let failure = self.failures.entry(key.clone()).or_default();
+    failure.count += 1;
+    failure.reason = reason;
+    failure.creep = creep.to_string();
+    if failure.count >= task_attempts(world) {
+      error!("Dropping a task that {}", failure);
+      self.failures.remove(&key);
+      return;
+    }
+    info!("Queueing a failed task again: {}", failure.reason);
+    self.request(task);
+  }
+
+  /// Get how the tasks that keep failing have gone, keyed by their last step
+  pub fn failures(&self) -> &HashMap<(Action, Position), TaskFailure> {
+    &self.failures
+  }
+
+  /// Forget the failures of a task a creep finished
+  pub fn task_done(&mut self, action: &Action, target: &Target) {
+    self.failures.remove(&(action.clone(), target.pos()));
+  }
+
+  /// Hand queued tasks to idle creeps that have the parts the task needs.
+  /// The creep closest to the first target wins, then the one that can carry
+  /// or do the most. Tasks that only power creeps can do go to power creeps.
+  /// Tasks no creep we have could ever take become spawn requests, the rest
+  /// wait for a capable creep to finish what it's doing.
+  pub fn assign_tasks(&mut self, creeps: &mut [MyCreepData]) {
+    self.spawn_requests.clear();
+    let queue = std::mem::take(&mut self.task_queue);
+    for task in queue {
+      // a task with nothing to do can't be assigned
+      let start = match task.front() {
+        Some((_, target)) => target.pos(),
+        None => continue,
+      };
+      let parts = task.parts_required();
+      let capacity = task.capacity_required();
+      let capable = |c: &MyCreepData| {
+        c.is_power_creep() == parts.is_empty()
+          && parts.iter().all(|g| g.iter().any(|p| c.active_parts(*p) > 0))
+          && c.capacity() >= capacity
+      };
+
+      // the creep that just failed the task isn't given it again
+      let failed_by = failure_key(&task)
+        .and_then(|key| self.failures.get(&key))
+        .map(|failure| failure.creep.clone());
+      let best = creeps
+        .iter_mut()
+        .filter(|c| c.task().is_empty() && capable(c))
+        .filter(|c| failed_by.as_deref() != Some(c.name()))
+        .min_by_key(|c| {
+          let range = c.pos().get_range_to(&start);
+          (range, std::cmp::Reverse(strength(c, &parts)))
+        });
+      if let Some(creep) = best {
+        info!("Assigning {} a task", creep.name());
+        creep.assign(task);
+        continue;
       }
+
+      // power creeps are spawned by their own manager
+      if !parts.is_empty() && !creeps.iter().any(|c| capable(c)) {
+        if let Some(room) = self.spawn_room(start) {
+          self.spawn_requests.push(SpawnRequest {
+            room,
+            parts,
+            capacity,
+            task: task.clone(),
+          });
+        }
+      }
+      self.task_queue.push_back(task);
     }
-    // This should never panic as we always have at least 1 spawn in the game
-    let username = game::spawns::values().get(0).unwrap().owner_name().unwrap();
-    let mut owned_cells = HashMap::new();
-    let mut scouted_cells = HashMap::new();
-    for room in game::rooms::values() {
-      if let Some(ctrl) = room.controller() as Option<StructureController> {
-        if ctrl.my() {
-          owned_cells.insert(room.name(), room.into());
-          continue;
+  }
+
+  /// Get the creeps queued tasks are waiting on.
+  /// These are rebuilt every tick so they're never saved.
+  pub fn spawn_requests(&self) -> &[SpawnRequest] {
+    &self.spawn_requests
+  }
+
+  /// The owned room with a spawn closest to a position
+  fn spawn_room(&self, pos: Position) -> Option<RoomName> {
+    self
+      .owned_rooms
+      .values()
+      .filter(|r| !r.structures(StructureType::Spawn).is_empty())
+      .min_by_key(|r| Position::new(25, 25, r.name()).get_range_to(&pos))
+      .map(|r| r.name())
+  }
+
+  /// Load the director from the newest snapshot that can be read,
+  /// falling back to the backup snapshot, then the old Memory.Director key
+  /// and finally to a fresh Director. The rooms are then read back from their
+  /// own segments over the next few ticks.
+  /// Fails only when the segments aren't active yet, which means we have to wait a tick.
+  pub fn load(
+    world: &mut dyn World,
+  ) -> std::result::Result<Self, SegmentError> {
+    let mut director = Director::load_snapshot(world)?;
+    director.load_rooms(world);
+    Ok(director)
+  }
+
+  /// Load everything but the rooms
+  fn load_snapshot(
+    world: &mut dyn World,
+  ) -> std::result::Result<Self, SegmentError> {
+    let snapshots = load_snapshots(world)?;
+
+    for (i, snapshot) in snapshots.iter().enumerate() {
+      let name = snapshot.describe();
+      match Director::from_saved(&snapshot.data) {
+        Ok(data) => {
+          if i == 0 {
+            info!("Loaded the Director from the {}", name);
+          } else {
+            warn!("Fell back to the Director backup {}", name);
+          }
+          trace!("\nDirector successfully deserialize from memory: {}", &data);
+          snapshot.used();
+          return Ok(data);
         }
+        Err(e) => warn!("Rejected the {}: {}", name, e),
       }
-      scouted_cells.insert(room.name(), room.into());
     }
 
-    let task_queue = VecDeque::new();
+    // Nothing in the segments so try where we used to save it.
+    if let Some(data) = world.memory_bytes(DIRECTOR_KEY) {
+      match Director::from_saved(&data) {
+        Ok(data) => {
+          info!("Loaded the Director from Memory.{}", DIRECTOR_KEY);
+          return Ok(data);
+        }
+        Err(e) => warn!("Rejected Memory.{}: {}", DIRECTOR_KEY, e),
+      }
+    }
 
-    Director {
-      username,
-      owned_rooms: owned_cells,
-      scouted_rooms: scouted_cells,
-      task_queue,
+    warn!("No usable Director snapshot, rebuilding it");
+    Ok(Director::new(world))
+  }
+
+  /// Load a director from a saved payload, upgrading older layouts.
+  /// The rooms only come along with layouts from before they had their own
+  /// segments.
+  pub fn from_saved(data: &[u8]) -> std::result::Result<Self, SchemaError> {
+    let (director, version) = decode::<Director>(data)?;
+    if version < SCHEMA_VERSION {
+      info!("Upgraded the Director from layout v{}", version);
     }
+    Ok(director)
   }
-}
 
-impl Director {
-  /// Update the director
-  pub fn update(&mut self) {
-    for room in game::rooms::values() {
-      if let Some(ctrl) = room.controller() as Option<StructureController> {
-        if ctrl.my() {
-          self.scouted_rooms.remove(&room.name());
-          self.owned_rooms.insert(room.name(), room.into());
-          continue;
+  /// Start reading back the rooms saved in their own segments.
+  /// Rooms that came along with an older layout are marked so they get saved
+  /// with the other rooms on the next save.
+  pub fn load_rooms(&mut self, world: &mut dyn World) {
+    let inline: Vec<RoomName> = self
+      .owned_rooms
+      .keys()
+      .chain(self.scouted_rooms.keys())
+      .cloned()
+      .collect();
+    for name in inline {
+      self.room_cache.mark(name);
+    }
+
+    self.room_cache.read_all();
+    self.read_rooms(world);
+  }
+
+  /// Read back the saved rooms whose segments are active, asking for the rest.
+  /// Each bucket falls back to its backup copy when the newest can't be
+  /// decoded.
+  pub fn read_rooms(&mut self, world: &mut dyn World) {
+    for (bucket, copies) in self.room_cache.read(world) {
+      for (i, copy) in copies.iter().enumerate() {
+        match unpack_rooms(&copy.data) {
+          Ok(rooms) => {
+            if i > 0 {
+              warn!("Fell back to the backup of bucket {}", bucket);
+            }
+            self.room_cache.used(bucket, copy.slot);
+            self.add_saved_rooms(rooms);
+            break;
+          }
+          Err(e) => warn!(
+            "Rejected the rooms in bucket {} {}: {}",
+            bucket,
+            copy.describe(),
+            e
+          ),
         }
       }
-      self.owned_rooms.remove(&room.name());
-      self.scouted_rooms.insert(room.name(), room.into());
     }
   }
 
-  /// Save the director
-  pub fn save(&self) -> bool {
-    if let Ok(data) = serialize(self) {
-      root().set(DIRECTOR_KEY, data);
-      true
-    } else {
-      false
+  /// Add the saved rooms that we don't have yet.
+  /// Rooms seen since the VM reset are newer so they're kept instead.
+  fn add_saved_rooms(&mut self, rooms: Vec<(RoomName, bool, RoomData)>) {
+    for (name, owned, room) in rooms {
+      if self.room(name).is_some() {
+        continue;
+      }
+      if owned {
+        self.owned_rooms.insert(name, room);
+      } else {
+        self.scouted_rooms.insert(name, room);
+      }
+    }
+  }
+
+  /// Pack every room in a bucket along with whether we own it
+  fn pack_rooms(&self, bucket: u32) -> bincode::Result<Vec<u8>> {
+    let owned = self.owned_rooms.iter().map(|(name, room)| (*name, true, room));
+    let scouted =
+      self.scouted_rooms.iter().map(|(name, room)| (*name, false, room));
+    let rooms: Vec<(RoomName, bool, &RoomData)> = owned
+      .chain(scouted)
+      .filter(|(name, ..)| bucket_of(*name) == bucket)
+      .collect();
+    encode(&rooms)
+  }
+
+  /// Write the buckets holding rooms that changed since they were last saved.
+  /// Returns false if any of them couldn't be written.
+  fn save_rooms(&mut self, world: &mut dyn World) -> bool {
+    let mut saved = true;
+    for bucket in self.room_cache.due() {
+      let result = match self.pack_rooms(bucket) {
+        Ok(data) => self
+          .room_cache
+          .save_bucket(world, bucket, &data)
+          .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+      };
+      if let Err(e) = result {
+        error!("Unable to save the rooms in bucket {}: {}", bucket, e);
+        saved = false;
+      }
+    }
+    saved
+  }
+
+  /// Pack the Director the way it's saved for `read_dump`: the snapshot
+  /// followed by each bucket of rooms, one per line
+  pub fn dump(&self) -> bincode::Result<String> {
+    let mut lines = vec![encode_bytes(&seal(&encode(self)?))];
+    for bucket in 0..ROOM_BUCKETS {
+      lines.push(encode_bytes(&seal(&self.pack_rooms(bucket)?)));
+    }
+    Ok(lines.join("\n"))
+  }
+
+  /// Read a Director packed by `dump`
+  #[cfg(any(test, feature = "native"))]
+  pub fn from_dump(text: &str) -> std::result::Result<Self, SchemaError> {
+    let mut lines = text.lines();
+    let unpack = |line: &str| {
+      let sealed = decode_bytes(line).ok_or(SchemaError::Damaged)?;
+      unseal(&sealed).map(|data| data.to_vec()).ok_or(SchemaError::Damaged)
+    };
+    let snapshot = unpack(lines.next().unwrap_or_default())?;
+    let mut director = Director::from_saved(&snapshot)?;
+    for (bucket, line) in lines.enumerate() {
+      match unpack(line).and_then(|data| unpack_rooms(&data)) {
+        Ok(rooms) => director.add_saved_rooms(rooms),
+        Err(e) => warn!("Rejected the rooms in bucket {}: {}", bucket, e),
+      }
+    }
+    Ok(director)
+  }
+
+  /// Save the rooms that changed, then the rest of the director if it
+  /// changed since the last save
+  pub fn save(&mut self, world: &mut dyn World) -> bool {
+    let rooms_saved = self.save_rooms(world);
+
+    let data = match encode(&*self) {
+      Ok(data) => data,
+      Err(e) => {
+        error!("Unable to serialize the Director: {}", e);
+        return false;
+      }
+    };
+    if !self.room_cache.rest_changed(&data) {
+      trace!("Nothing changed since the Director was last saved");
+      return rooms_saved;
     }
+
+    let time = world.time();
+    match save_snapshot(world, &data, time) {
+      Ok(()) => {
+        // Anything left over from before segments were used is stale now.
+        world.delete_memory(DIRECTOR_KEY);
+        self.room_cache.saved(data);
+        rooms_saved
+      }
+      Err(e) => {
+        error!("Unable to save the Director: {}", e);
+        false
+      }
+    }
+  }
+}
+
+/// Decode the rooms packed by `Director::pack_rooms`
+fn unpack_rooms(
+  data: &[u8],
+) -> std::result::Result<Vec<(RoomName, bool, RoomData)>, SchemaError> {
+  decode::<Vec<(RoomName, bool, RoomData)>>(data).map(|(rooms, _)| rooms)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn mine() -> RoomName {
+    RoomName::new("W1N1").unwrap()
+  }
+
+  fn theirs() -> RoomName {
+    RoomName::new("W2N1").unwrap()
+  }
+
+  /// A room of ours with a creep in it next to one we've only scouted
+  fn world() -> MockWorld {
+    let mut world = MockWorld::new("Player");
+    let mut data = RoomData::new(mine(), 1);
+    let pos = Position::new(25, 25, mine());
+    data.add_creep(CommonCreepData::new("Jim", mock_id(1), pos, &[Move]));
+    world.add_room(MockRoom::new(data, true));
+    world.add_room(MockRoom::new(RoomData::new(theirs(), 0), false));
+    world
+  }
+
+  #[test]
+  fn update_sorts_rooms_by_owner() {
+    let mut world = world();
+    let mut director = Director::new(&world);
+    assert!(director.owned_rooms().contains_key(&mine()));
+    assert!(director.scouted_rooms().contains_key(&theirs()));
+
+    world.rooms.get_mut(&theirs()).unwrap().mine = true;
+    director.update(&world);
+    assert!(director.owned_rooms().contains_key(&theirs()));
+    assert!(!director.scouted_rooms().contains_key(&theirs()));
+  }
+
+  /// The room segments that were written
+  fn room_segments(world: &MockWorld) -> Vec<u32> {
+    let mut ids: Vec<u32> = world
+      .segments
+      .keys()
+      .cloned()
+      .filter(|id| *id >= ROOM_FIRST_SEGMENT)
+      .collect();
+    ids.sort();
+    ids
+  }
+
+  fn segment_of(name: RoomName, slot: u32) -> u32 {
+    bucket_segment(bucket_of(name), slot)
+  }
+
+  #[test]
+  fn save_writes_only_changed_rooms() {
+    let mut world = world();
+    let mut director = Director::new(&world);
+    director.save(&mut world);
+    let mut both = vec![segment_of(mine(), 1), segment_of(theirs(), 1)];
+    both.sort();
+    both.dedup();
+    assert_eq!(room_segments(&world), both);
+
+    // creeps moving around isn't worth saving the room again
+    world.segments.clear();
+    world.creep_mut("Jim").unwrap().set_pos(Position::new(26, 25, mine()));
+    director.update(&world);
+    director.save(&mut world);
+    assert!(room_segments(&world).is_empty());
+
+    world.rooms.get_mut(&mine()).unwrap().data.set_level(2);
+    director.update(&world);
+    director.save(&mut world);
+    // the new copy goes next to the old one, which is kept as a backup
+    assert_eq!(room_segments(&world), vec![segment_of(mine(), 0)]);
+  }
+
+  #[test]
+  fn rooms_are_read_back_a_few_segments_a_tick() {
+    let mut world = world();
+    let mut director = Director::new(&world);
+    director.save(&mut world);
+
+    let mut loaded = Director::from_saved(&encode(&director).unwrap()).unwrap();
+    loaded.load_rooms(&mut world);
+    assert!(loaded.owned_rooms().is_empty());
+    assert_eq!(world.requested_segments.len(), MAX_SEGMENTS as usize);
+
+    let mut ticks = 0;
+    while !world.requested_segments.is_empty() {
+      world.next_tick();
+      loaded.read_rooms(&mut world);
+      ticks += 1;
+      assert!(ticks <= ROOM_BUCKETS * SLOTS / MAX_SEGMENTS);
+    }
+    assert_eq!(loaded.owned_rooms(), director.owned_rooms());
+    assert_eq!(loaded.scouted_rooms(), director.scouted_rooms());
+  }
+
+  #[test]
+  fn unread_rooms_are_not_overwritten() {
+    let mut world = world();
+    let mut director = Director::new(&world);
+    director.save(&mut world);
+    let saved = world.segments.clone();
+
+    let mut loaded = Director::from_saved(&encode(&director).unwrap()).unwrap();
+    loaded.load_rooms(&mut world);
+    world.rooms.get_mut(&theirs()).unwrap().data.set_level(1);
+    loaded.update(&world);
+    loaded.save(&mut world);
+    for slot in 0..SLOTS {
+      let id = segment_of(theirs(), slot);
+      assert_eq!(world.segments.get(&id), saved.get(&id));
+    }
+  }
+
+  /// Read every saved room back into a Director loaded from a snapshot
+  fn reload(world: &mut MockWorld, director: &Director) -> Director {
+    let mut loaded = Director::from_saved(&encode(director).unwrap()).unwrap();
+    loaded.load_rooms(world);
+    while !world.requested_segments.is_empty() {
+      world.next_tick();
+      loaded.read_rooms(world);
+    }
+    loaded
+  }
+
+  #[test]
+  fn damaged_rooms_fall_back_to_their_backup() {
+    let mut world = world();
+    let mut director = Director::new(&world);
+    director.save(&mut world);
+    world.time += 1;
+    world.rooms.get_mut(&mine()).unwrap().data.set_level(2);
+    director.update(&world);
+    director.save(&mut world);
+
+    world.damage_segment(segment_of(mine(), 0));
+    let mut loaded = reload(&mut world, &director);
+    assert_eq!(loaded.room(mine()).map(|room| room.level()), Some(1));
+
+    // the backup we're running from isn't overwritten by the next save
+    let backup = world.segments[&segment_of(mine(), 1)].clone();
+    loaded.room_cache.mark(mine());
+    loaded.save(&mut world);
+    assert_eq!(world.segments[&segment_of(mine(), 1)], backup);
+  }
+
+  fn scout(x: u32) -> Task {
+    let pos = |x| Position::new(x, 25, mine());
+    let mut task = Task::default();
+    task.push_back((Action::Scout, Target::Path([pos(25), pos(x)])));
+    task
+  }
+
+  #[test]
+  fn failures_are_kept_across_resets() {
+    let mut world = world();
+    let mut director = Director::new(&world);
+    director.task_failed(&world, scout(30), "Jim", "lost".to_string());
+    let mut loaded = reload(&mut world, &director);
+    let failure = &loaded.failures()[&failure_key(&scout(30)).unwrap()];
+    assert_eq!((failure.count, failure.creep.as_str()), (1, "Jim"));
+    assert_eq!(failure.reason, "lost");
+
+    // the third failure drops it
+    loaded.task_queue.clear();
+    loaded.task_failed(&world, scout(30), "Jim", "lost".to_string());
+    assert_eq!(loaded.task_queue().len(), 1);
+    loaded.task_queue.clear();
+    loaded.task_failed(&world, scout(30), "Jim", "lost".to_string());
+    assert!(loaded.task_queue().is_empty());
+    assert!(loaded.failures().is_empty());
+  }
+
+  #[test]
+  fn failed_tasks_go_to_another_creep() {
+    let mut world = world();
+    let pos = Position::new(40, 25, mine());
+    let bob = CommonCreepData::new("Bob", mock_id(2), pos, &[Move]);
+    world.rooms.get_mut(&mine()).unwrap().data.add_creep(bob);
+    let mut director = Director::new(&world);
+    director.task_failed(&world, scout(30), "Jim", "lost".to_string());
+
+    let mut creeps = world.my_creeps();
+    director.assign_tasks(&mut creeps);
+    let busy: Vec<&str> = creeps
+      .iter()
+      .filter(|c| !c.task().is_empty())
+      .map(|c| c.name())
+      .collect();
+    // Jim is closer but just failed it
+    assert_eq!(busy, vec!["Bob"]);
+  }
+
+  #[test]
+  fn dump_round_trips() {
+    let world = world();
+    let director = Director::new(&world);
+    let loaded = Director::from_dump(&director.dump().unwrap()).unwrap();
+    assert_eq!(loaded.owned_rooms(), director.owned_rooms());
+    assert_eq!(loaded.scouted_rooms(), director.scouted_rooms());
   }
 }
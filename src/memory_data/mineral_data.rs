@@ -1,7 +1,7 @@
 use crate::*;
 
 /// This serializes mineral data
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 pub struct MineralData {
   pos: CommonData,
   id: ObjectId<Mineral>,
@@ -36,6 +36,16 @@ impl From<Mineral> for MineralData {
 }
 
 impl MineralData {
+  /// Get the id of the mineral
+  pub fn id(&self) -> ObjectId<Mineral> {
+    self.id
+  }
+
+  /// Get the amount of mineral left
+  pub fn amount(&self) -> u32 {
+    self.amount
+  }
+
   /// Unwrap the mineral
   pub fn unwrap(&self) -> Mineral {
     game::get_object_typed(self.id).unwrap().unwrap()
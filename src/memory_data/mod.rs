@@ -2,30 +2,40 @@ pub use action::*;
 pub use common_creep_data::*;
 pub use common_data::*;
 pub use construction_data::*;
+pub use controller_data::*;
 pub use deposit_data::*;
 pub use director::*;
 pub use mineral_data::*;
 pub use my_creep_data::*;
+pub use power_creep_data::*;
+pub use resource_data::*;
 pub use room_data::*;
 pub use ruin_data::*;
 pub use source_data::*;
+pub use spawn_request::*;
 pub use structure_data::*;
 pub use target::*;
 pub use task::*;
+pub use task_failure::*;
 pub use tombstone_data::*;
 
 mod action;
 mod common_creep_data;
 mod common_data;
 mod construction_data;
+mod controller_data;
 mod deposit_data;
 mod director;
 mod mineral_data;
 mod my_creep_data;
+mod power_creep_data;
+mod resource_data;
 mod room_data;
 mod ruin_data;
 mod source_data;
+mod spawn_request;
 mod structure_data;
 mod target;
 mod task;
+mod task_failure;
 mod tombstone_data;
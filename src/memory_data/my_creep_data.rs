@@ -1,12 +1,47 @@
 use crate::*;
+use screeps::Terrain;
+use std::cell::RefCell;
 
-const TASK_KEY: &str = "task";
+/// Where each creep keeps its task in its memory
+pub const TASK_KEY: &str = "task";
+/// Where each creep keeps the tick it finished its last task in its memory
+pub const IDLE_KEY: &str = "idle";
+
+thread_local! {
+  /// The tick along with the energy spent upgrading each max level controller
+  /// during it
+  static UPGRADED: RefCell<(u32, HashMap<RoomName, u32>)> =
+    RefCell::new((0, HashMap::new()));
+
+  /// Where each scout started along with the tiles it has left to walk and
+  /// the ones it walked. The task keeps the last tile reached so a scout
+  /// carries on from there when this is lost to a VM reset.
+  static SCOUTING: RefCell<HashMap<String, Scouting>> =
+    RefCell::new(HashMap::new());
+
+  /// How often each creep's current step has been blocked and the tick to
+  /// try it again on
+  static RETRIES: RefCell<HashMap<String, Retry>> = RefCell::new(HashMap::new());
+}
+
+/// Where a scout started, the tiles it has left and the tiles it walked
+type Scouting = (Position, Vec<Position>, Vec<Position>);
+
+/// How often a step has been blocked in a row
+#[derive(Default)]
+struct Retry {
+  attempts: u32,
+  until: u32,
+}
 
 /// Extends common creep data to add tasks to creeps
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 pub struct MyCreepData {
   parent: CommonCreepData,
   task: Task,
+  /// The tick the last task was finished on, None while busy or if the creep
+  /// was never given one
+  idle_since: Option<u32>,
 }
 
 impl Deref for MyCreepData {
@@ -19,29 +54,68 @@ impl Deref for MyCreepData {
 
 impl From<Creep> for MyCreepData {
   fn from(c: Creep) -> Self {
-    let task = if let Ok(Some(t)) = c.memory().arr(TASK_KEY) {
-      deserialize::<Task>(&t).unwrap_or_default()
-    } else {
-      Task::default()
-    };
+    let task = saved_task(&c.memory());
+    let idle_since = saved_idle(&c.memory());
     let parent = c.into();
-    MyCreepData { parent, task }
+    MyCreepData { parent, task, idle_since }
   }
 }
 
 impl From<PowerCreep> for MyCreepData {
   fn from(c: PowerCreep) -> Self {
-    let task = if let Ok(Some(t)) = c.memory().arr(TASK_KEY) {
-      deserialize::<Task>(&t).unwrap_or_default()
-    } else {
-      Task::default()
-    };
+    let task = saved_task(&c.memory());
+    let idle_since = saved_idle(&c.memory());
     let parent = c.into();
-    MyCreepData { parent, task }
+    MyCreepData { parent, task, idle_since }
+  }
+}
+
+/// Read the task saved in a creep's memory
+fn saved_task(memory: &MemoryReference) -> Task {
+  if let Ok(Some(text)) = memory.string(TASK_KEY) {
+    return Task::from_memory(&text).unwrap_or_default();
+  }
+  // tasks used to be read as a plain array of bytes
+  match memory.arr(TASK_KEY) {
+    // they had no header so they're read as the oldest layout
+    Ok(Some(data)) => {
+      decode::<Task>(&data).map(|(task, _)| task).unwrap_or_default()
+    }
+    _ => Task::default(),
   }
 }
 
+/// Read the tick a creep finished its last task on out of its memory
+fn saved_idle(memory: &MemoryReference) -> Option<u32> {
+  memory.i32(IDLE_KEY).ok().flatten().map(|tick| tick as u32)
+}
+
 impl MyCreepData {
+  /// Pair creep data with a task and the tick it finished its last one on
+  pub fn new(
+    parent: CommonCreepData,
+    task: Task,
+    idle_since: Option<u32>,
+  ) -> Self {
+    MyCreepData { parent, task, idle_since }
+  }
+
+  /// Get the current task
+  pub fn task(&self) -> &Task {
+    &self.task
+  }
+
+  /// The tick the last task was finished on while there's no new one
+  pub fn idle_since(&self) -> Option<u32> {
+    self.idle_since
+  }
+
+  /// Replace the current task
+  pub fn assign(&mut self, task: Task) {
+    self.idle_since = None;
+    self.task = task;
+  }
+
   /// Unwrap my creep
   pub fn unwrap_creep(&self) -> Creep {
     game::creeps::get(&self.name()).unwrap()
@@ -52,75 +126,1390 @@ impl MyCreepData {
     game::power_creeps::get(&self.name()).unwrap()
   }
 
-  /// Run assigned task or recycle this creep if the task is complete
-  pub fn run(&mut self) {
-    // TODO clean this up and split it up
-    let creep = self.unwrap_creep();
-    if let Some((a, target)) = self.task.pop_front() {
-      match a {
-        Action::Harvest => {
-          if target.same_room(&self.parent) {
-            // normal harvest
-            if let Some(t) = target.as_harvestable() {
-              let code = creep.harvest(t.as_ref());
-              if code == ReturnCode::NotInRange {
-                creep.move_to(t.as_ref());
-                creep.harvest(t.as_ref());
-              } else if code != ReturnCode::Ok {
-                warn!("Problem harvesting @ {:?}", target);
-              }
-              // check if the task is completed
-              if creep.store_free_capacity(None) == 0 {
-                return;
-              }
-              match &target {
-                Target::Source(s) => {
-                  if s.unwrap().energy() == 0 {
-                    return;
-                  } else {
-                    self.task.push_front((a, target));
-                  }
-                }
-                Target::Deposit(_) => {
-                  return;
-                }
-                Target::Mineral(m) => {
-                  if m.unwrap().mineral_amount() == 0 {
-                    return;
-                  } else {
-                    self.task.push_front((a, target));
-                  }
-                }
-                _ => return,
-              }
-            }
-          } else {
-            target.move_to(&creep);
+  /// Run the next step of the assigned task or recycle this creep once it
+  /// has been idle too long.
+  /// Blocked steps are retried after a backoff that doubles each time. A step
+  /// that fails or stays blocked too long hands the rest of the task back to
+  /// the Director.
+  pub fn run(&mut self, world: &mut dyn World, director: &mut Director) {
+    let name = self.name().to_string();
+    if let Some(creep) = world.creep(&name) {
+      self.parent = creep;
+    }
+
+    let time = world.time();
+    if self.task.is_empty() {
+      if self.worn_out(world) {
+        self.recycle(world, director);
+      }
+      return;
+    }
+    let backing_off = RETRIES.with(|retries| {
+      retries.borrow().get(&name).map_or(false, |retry| retry.until > time)
+    });
+    if backing_off {
+      return;
+    }
+
+    let (action, mut target) = self.task.pop_front().expect("there's a step");
+    let outcome = match action {
+      Action::Harvest => self.harvest(world, &name, &mut target),
+      Action::Mine => self.mine(world, director, &name, &mut target),
+      Action::Attack => self.attack(world, director, &name, &mut target),
+      Action::AttackController => {
+        self.control(world, director, &name, action.clone(), &mut target)
+      }
+      Action::Build => self.build(world, &name, &mut target),
+      Action::Claim => {
+        self.control(world, director, &name, action.clone(), &mut target)
+      }
+      Action::Dismantle(forced) => {
+        self.dismantle(world, director, &name, forced, &mut target)
+      }
+      Action::GenerateSafeMode => {
+        self.generate_safe_mode(world, &name, &mut target)
+      }
+      Action::Heal => self.heal(world, &name, &mut target),
+      Action::Pickup => self.pickup(world, &name, &mut target),
+      Action::Repair => self.repair(world, director, &name, &mut target),
+      Action::Reserve => {
+        self.control(world, director, &name, action.clone(), &mut target)
+      }
+      Action::Transfer(r, amount) => {
+        self.transfer(world, &name, &mut target, r, amount)
+      }
+      Action::Withdraw(r, amount) => {
+        self.withdraw(world, &name, &mut target, r, amount)
+      }
+      Action::Scout => self.scout(world, director, &name, &mut target),
+      Action::Upgrade => self.upgrade(world, director, &name, &mut target),
+      Action::Renew => self.renew(world, &name, &mut target),
+      Action::EnableRoom => self.enable_room(world, &name, &mut target),
+      Action::UsePower(power) => {
+        self.use_power(world, &name, power, &mut target)
+      }
+    };
+
+    match outcome {
+      StepOutcome::InProgress => {
+        self.task.push_front((action, target));
+        RETRIES.with(|retries| retries.borrow_mut().remove(&name));
+      }
+      StepOutcome::Done => {
+        RETRIES.with(|retries| retries.borrow_mut().remove(&name));
+        if self.task.is_empty() {
+          self.idle_since = Some(time);
+          director.task_done(&action, &target);
+        }
+      }
+      StepOutcome::Blocked => {
+        let attempts = RETRIES.with(|retries| {
+          let mut retries = retries.borrow_mut();
+          let retry = retries.entry(name.clone()).or_default();
+          retry.attempts += 1;
+          retry.until = time + backoff(retry.attempts);
+          retry.attempts
+        });
+        self.task.push_front((action, target));
+        if attempts >= step_attempts(world) {
+          self.fail(world, director, format!("blocked {} times", attempts));
+        }
+      }
+      StepOutcome::Failed(reason) => {
+        self.task.push_front((action, target));
+        self.fail(world, director, reason);
+      }
+    }
+  }
+
+  /// Give what's left of the task back to the Director along with why it
+  /// couldn't be finished
+  fn fail(
+    &mut self,
+    world: &dyn World,
+    director: &mut Director,
+    reason: String,
+  ) {
+    let name = self.name().to_string();
+    RETRIES.with(|retries| retries.borrow_mut().remove(&name));
+    SCOUTING.with(|s| s.borrow_mut().remove(&name));
+    warn!("{} gave up on its task: {}", name, reason);
+    self.idle_since = Some(world.time());
+    let task = std::mem::take(&mut self.task);
+    director.task_failed(world, task, &name, reason);
+  }
+
+  /// An idle creep is worth recycling once it's about to die or has gone
+  /// `idle_ticks` without a new task. Creeps that were never given a task are
+  /// still waiting for their first one so they're kept.
+  fn worn_out(&self, world: &dyn World) -> bool {
+    match self.idle_since {
+      Some(since) => {
+        self.ticks_to_live() < recycle_ttl(world)
+          || world.time().saturating_sub(since) >= idle_ticks(world)
+      }
+      None => false,
+    }
+  }
+
+  /// Harvest a source or mineral until we're full or it runs dry
+  fn harvest(
+    &mut self,
+    world: &mut dyn World,
+    name: &str,
+    target: &mut Target,
+  ) -> StepOutcome {
+    if !target.same_room(&self.parent) {
+      return move_to(world, name, target.pos());
+    }
+
+    match world.intent(name, Intent::Harvest(target.clone())) {
+      // it's empty, which is checked below
+      ReturnCode::Ok | ReturnCode::NotEnoughResources => {}
+      ReturnCode::NotInRange => {
+        let moved = move_to(world, name, target.pos());
+        if moved != StepOutcome::InProgress {
+          return moved;
+        }
+      }
+      code => {
+        return unexpected(code, format!("harvesting @ {}", target.pos()))
+      }
+    }
+
+    // check if the task is completed
+    if self.parent.free_capacity() == 0 {
+      return StepOutcome::Done;
+    }
+    match world.refresh(target) {
+      Some(Target::Source(s)) if s.amount() > 0 => StepOutcome::InProgress,
+      Some(Target::Mineral(m)) if m.amount() > 0 => StepOutcome::InProgress,
+      _ => StepOutcome::Done,
+    }
+  }
+
+  /// Mine a source or mineral from a fixed tile for the rest of our life
+  fn mine(
+    &mut self,
+    world: &mut dyn World,
+    director: &Director,
+    name: &str,
+    target: &mut Target,
+  ) -> StepOutcome {
+    // static miners never finish so the step is always kept
+    let room = match director.room(target.pos().room_name()) {
+      Some(room) => room,
+      None => return move_to(world, name, target.pos()),
+    };
+    let spot = match mining_spot(world, room, target.pos()) {
+      Some(spot) => spot,
+      // every tile next to it is taken
+      None => return StepOutcome::Blocked,
+    };
+    if self.pos() != spot {
+      return move_to(world, name, spot);
+    }
+
+    // empty out before the next harvest would overflow
+    if self.capacity() > 0 && self.free_capacity() < self.harvesting_power() {
+      if let (Some(r), Some(drop_off)) = (self.carried(), drop_off(room, spot))
+      {
+        let target = Target::Structure(drop_off.clone());
+        world.intent(name, Intent::Transfer(target, r, None));
+      }
+    }
+
+    match world.intent(name, Intent::Harvest(target.clone())) {
+      // waiting for the source to regenerate or the extractor to cool down
+      ReturnCode::Ok | ReturnCode::NotEnoughResources | ReturnCode::Tired => {
+        StepOutcome::InProgress
+      }
+      code => unexpected(code, format!("mining @ {}", target.pos())),
+    }
+  }
+
+  /// A copy of our mining step once it's time to spawn a replacement, so it
+  /// arrives just as we die
+  pub fn replacement(&self, director: &Director) -> Option<Task> {
+    let step = match self.task.front() {
+      Some(step) if step.0 == Action::Mine => step.clone(),
+      _ => return None,
+    };
+    let home = home_room(self.name()).unwrap_or_else(|| self.pos().room_name());
+    let spawn = director
+      .room(home)?
+      .structures(StructureType::Spawn)
+      .iter()
+      .map(|s| s.pos())
+      .min_by_key(|pos| pos.get_range_to(&step.1.pos()))?;
+
+    // moving one tile a tick along with some slack for winding paths
+    let travel = spawn.get_range_to(&step.1.pos()) * 3 / 2;
+    let spawning = self.body_size() * CREEP_SPAWN_TIME;
+    if self.ticks_to_live() <= travel + spawning {
+      let mut task = Task::default();
+      task.push_back(step);
+      Some(task)
+    } else {
+      None
+    }
+  }
+
+  /// Head for the room a position is in along the route between rooms.
+  /// Returns how the move went while we're still on the way.
+  fn travel(
+    &self,
+    world: &mut dyn World,
+    name: &str,
+    pos: Position,
+  ) -> Option<StepOutcome> {
+    let here = self.pos().room_name();
+    if here == pos.room_name() {
+      return None;
+    }
+    let next = match world.route(here, pos.room_name()).first() {
+      // aim for the middle of the next room so each leg stays short
+      Some(next) if *next != pos.room_name() => Position::new(25, 25, *next),
+      Some(_) => pos,
+      None => {
+        let reason = format!("no route from {} to {}", here, pos.room_name());
+        return Some(StepOutcome::Failed(reason));
+      }
+    };
+    Some(move_to(world, name, next))
+  }
+
+  /// Walk a scouted path from one end to the other, snapshotting each room
+  /// we enter and handing the tiles we walked to the Director at the end
+  fn scout(
+    &mut self,
+    world: &mut dyn World,
+    director: &mut Director,
+    name: &str,
+    target: &mut Target,
+  ) -> StepOutcome {
+    let (from, to) = match *target {
+      Target::Path([from, to]) => (from, to),
+      _ => return StepOutcome::Failed(format!("unable to scout {:?}", target)),
+    };
+    let here = self.pos();
+    let progress = SCOUTING.with(|s| s.borrow_mut().remove(name));
+    let (start, mut path, mut walked) = match progress {
+      Some(progress) => progress,
+      // the ends are often objects that can't be stood on so being next to
+      // the start is enough. It's also where a scout whose progress was lost
+      // got to, since the start follows it along.
+      None if here.get_range_to(&from) <= 1 => {
+        director.observe(world, here.room_name());
+        (from, world.scout_path(here, to), vec![here])
+      }
+      None => {
+        return match self.travel(world, name, from) {
+          Some(outcome) => outcome,
+          None => move_to(world, name, from),
+        }
+      }
+    };
+
+    if walked.last() != Some(&here) {
+      if walked.last().map(|last| last.room_name()) != Some(here.room_name()) {
+        director.observe(world, here.room_name());
+      }
+      walked.push(here);
+    }
+    // drop everything up to where we're standing
+    if let Some(i) = path.iter().position(|tile| *tile == here) {
+      path.drain(..=i);
+    }
+
+    match path.first() {
+      Some(next) => {
+        let outcome = move_to(world, name, *next);
+        if let StepOutcome::Failed(_) = outcome {
+          return outcome;
+        }
+        *target = Target::Path([here, to]);
+        SCOUTING.with(|s| {
+          s.borrow_mut().insert(name.to_string(), (start, path, walked))
+        });
+        outcome
+      }
+      None => {
+        director.record_path(start, to, walked);
+        StepOutcome::Done
+      }
+    }
+  }
+
+  /// Claim, reserve or attack a controller in any room
+  fn control(
+    &mut self,
+    world: &mut dyn World,
+    director: &Director,
+    name: &str,
+    action: Action,
+    target: &mut Target,
+  ) -> StepOutcome {
+    if let Some(outcome) = self.travel(world, name, target.pos()) {
+      return outcome;
+    }
+    let ctrl = match world.refresh(target) {
+      Some(Target::Controller(ctrl)) => ctrl,
+      _ => {
+        let reason = format!("no controller @ {}", target.pos());
+        return StepOutcome::Failed(reason);
+      }
+    };
+
+    let me = director.username();
+    let theirs = |player: Option<&str>| player.map_or(false, |p| p != me);
+    let room = ctrl.pos().room_name();
+    let intent = match action {
+      Action::Claim => {
+        if ctrl.my() {
+          return StepOutcome::Done;
+        }
+        if theirs(ctrl.owner()) || theirs(ctrl.reserved_by()) {
+          let reason =
+            format!("{} has to be attacked before it's claimed", room);
+          return StepOutcome::Failed(reason);
+        }
+        Intent::Claim(target.clone())
+      }
+      Action::Reserve => {
+        if ctrl.owner().is_some() {
+          return StepOutcome::Done;
+        }
+        if theirs(ctrl.reserved_by()) {
+          let reason =
+            format!("{} has to be attacked before it's reserved", room);
+          return StepOutcome::Failed(reason);
+        }
+        Intent::Reserve(target.clone())
+      }
+      Action::AttackController => {
+        // nothing left to take away
+        if ctrl.my() || (!theirs(ctrl.owner()) && !theirs(ctrl.reserved_by())) {
+          return StepOutcome::Done;
+        }
+        Intent::AttackController(target.clone())
+      }
+      _ => {
+        let reason = format!("{:?} isn't done to a controller", action);
+        return StepOutcome::Failed(reason);
+      }
+    };
+    *target = Target::Controller(ctrl.clone());
+
+    // wait next to the controller while it can't be attacked again or our
+    // reservation is already at the max
+    let reserved = ctrl.reservation_ticks(world.time());
+    let waiting = match action {
+      Action::AttackController => ctrl.upgrade_blocked() > 0,
+      Action::Reserve => {
+        reserved + self.active_parts(Claim) > CONTROLLER_RESERVE_MAX
+      }
+      _ => false,
+    };
+    if waiting {
+      if self.pos().get_range_to(&ctrl.pos()) > 1 {
+        return move_to(world, name, ctrl.pos());
+      }
+      return StepOutcome::InProgress;
+    }
+
+    match world.intent(name, intent) {
+      // reservers keep topping the reservation up until they die
+      ReturnCode::Ok if action == Action::Reserve => StepOutcome::InProgress,
+      ReturnCode::Ok => StepOutcome::Done,
+      ReturnCode::NotInRange => move_to(world, name, ctrl.pos()),
+      code => unexpected(code, format!("{:?} on {}", action, room)),
+    }
+  }
+
+  /// Hand the ghodium we carry to a controller for another safe mode
+  fn generate_safe_mode(
+    &mut self,
+    world: &mut dyn World,
+    name: &str,
+    target: &mut Target,
+  ) -> StepOutcome {
+    if self.store_of(Ghodium) < SAFE_MODE_GHODIUM {
+      let reason = "not enough ghodium for a safe mode".to_string();
+      return StepOutcome::Failed(reason);
+    }
+    if let Some(outcome) = self.travel(world, name, target.pos()) {
+      return outcome;
+    }
+
+    match world.intent(name, Intent::GenerateSafeMode(target.clone())) {
+      ReturnCode::Ok => StepOutcome::Done,
+      ReturnCode::NotInRange => move_to(world, name, target.pos()),
+      code => {
+        unexpected(code, format!("generating safe mode @ {}", target.pos()))
+      }
+    }
+  }
+
+  /// Renew a power creep at a power spawn
+  fn renew(
+    &mut self,
+    world: &mut dyn World,
+    name: &str,
+    target: &mut Target,
+  ) -> StepOutcome {
+    if let Some(outcome) = self.travel(world, name, target.pos()) {
+      return outcome;
+    }
+    match world.intent(name, Intent::Renew(target.clone())) {
+      ReturnCode::Ok => StepOutcome::Done,
+      ReturnCode::NotInRange => move_to(world, name, target.pos()),
+      code => unexpected(code, format!("renewing @ {}", target.pos())),
+    }
+  }
+
+  /// Enable powers in the room of a controller
+  fn enable_room(
+    &mut self,
+    world: &mut dyn World,
+    name: &str,
+    target: &mut Target,
+  ) -> StepOutcome {
+    if let Some(outcome) = self.travel(world, name, target.pos()) {
+      return outcome;
+    }
+    match world.refresh(target) {
+      Some(Target::Controller(ctrl)) if ctrl.power_enabled() => {
+        return StepOutcome::Done
+      }
+      _ => {}
+    }
+    match world.intent(name, Intent::EnableRoom(target.clone())) {
+      ReturnCode::Ok => StepOutcome::Done,
+      ReturnCode::NotInRange => move_to(world, name, target.pos()),
+      code => unexpected(code, format!("enabling the room @ {}", target.pos())),
+    }
+  }
+
+  /// Use a power on a target.
+  /// Powers that don't need a target are used on the spot.
+  fn use_power(
+    &mut self,
+    world: &mut dyn World,
+    name: &str,
+    power: PowerType,
+    target: &mut Target,
+  ) -> StepOutcome {
+    let on = match power {
+      PowerType::GenerateOps => None,
+      _ => Some(target.clone()),
+    };
+    if on.is_some() {
+      if let Some(outcome) = self.travel(world, name, target.pos()) {
+        return outcome;
+      }
+    }
+    match world.intent(name, Intent::UsePower(power, on)) {
+      ReturnCode::Ok => StepOutcome::Done,
+      ReturnCode::NotInRange => move_to(world, name, target.pos()),
+      code => unexpected(code, format!("using {:?} @ {}", power, target.pos())),
+    }
+  }
+
+  /// Fight a creep or structure until it dies or leaves the room.
+  /// Melee and ranged parts are both used and ranged parts switch to a mass
+  /// attack when that would do more damage.
+  fn attack(
+    &mut self,
+    world: &mut dyn World,
+    director: &Director,
+    name: &str,
+    target: &mut Target,
+  ) -> StepOutcome {
+    if let Some(outcome) = self.travel(world, name, target.pos()) {
+      return outcome;
+    }
+    let room_name = self.pos().room_name();
+    *target = match world.refresh(target) {
+      Some(target) if target.pos().room_name() == room_name => target,
+      // it died or got away
+      _ => return StepOutcome::Done,
+    };
+    let room = match director.room(room_name) {
+      Some(room) => room,
+      None => return StepOutcome::Done,
+    };
+
+    let range = self.pos().get_range_to(&target.pos());
+    let melee = self.attack_power() > 0;
+    let ranged = self.ranged_attack_power() > 0;
+
+    if melee && range <= 1 {
+      world.intent(name, Intent::Attack(target.clone()));
+    }
+    if ranged && range <= 3 {
+      let mass: u32 = room
+        .other_creeps()
+        .iter()
+        .map(|c| self.mass_attack_power(self.pos().get_range_to(&c.pos())))
+        .sum();
+      if mass > self.ranged_attack_power() {
+        world.intent(name, Intent::RangedMassAttack);
+      } else {
+        world.intent(name, Intent::RangedAttack(target.clone()));
+      }
+    }
+
+    // healing takes the place of a melee attack so only ranged fighters do
+    // it while fighting
+    if !melee && self.heal_power() > 0 && self.hits() < self.max_hits() {
+      let me = Target::Creep(self.parent.clone());
+      world.intent(name, Intent::Heal(me));
+    }
+
+    // melee fighters close in while ranged ones hold at range 3
+    let reach = if melee { 1 } else { 3 };
+    if range > reach {
+      return move_to(world, name, target.pos());
+    }
+    StepOutcome::InProgress
+  }
+
+  /// Stay with an ally and keep it healed, healing ourselves first when
+  /// we've taken more damage
+  fn heal(
+    &mut self,
+    world: &mut dyn World,
+    name: &str,
+    target: &mut Target,
+  ) -> StepOutcome {
+    if let Some(outcome) = self.travel(world, name, target.pos()) {
+      return outcome;
+    }
+    let room_name = self.pos().room_name();
+    let ally = match world.refresh(target) {
+      Some(Target::Creep(ally)) if ally.pos().room_name() == room_name => ally,
+      // it died or left the room
+      _ => return StepOutcome::Done,
+    };
+
+    let range = self.pos().get_range_to(&ally.pos());
+    let hurt = self.max_hits() - self.hits();
+    let ally_hurt = ally.max_hits() - ally.hits();
+    *target = Target::Creep(ally.clone());
+
+    if hurt > 0 && (hurt >= ally_hurt || range > 3) {
+      let me = Target::Creep(self.parent.clone());
+      world.intent(name, Intent::Heal(me));
+    } else if ally_hurt > 0 && range <= 1 {
+      world.intent(name, Intent::Heal(target.clone()));
+    } else if ally_hurt > 0 && range <= 3 {
+      world.intent(name, Intent::RangedHeal(target.clone()));
+    }
+
+    if range > 1 {
+      return move_to(world, name, ally.pos());
+    }
+    StepOutcome::InProgress
+  }
+
+  /// Take a structure apart and then pick up whatever it dropped
+  fn dismantle(
+    &mut self,
+    world: &mut dyn World,
+    director: &Director,
+    name: &str,
+    forced: bool,
+    target: &mut Target,
+  ) -> StepOutcome {
+    if let Some(outcome) = self.travel(world, name, target.pos()) {
+      return outcome;
+    }
+    let structure = match world.refresh(target) {
+      Some(Target::Structure(s)) => s,
+      // it's gone so collect what's left of it
+      _ => {
+        self.salvage(world, director, target.pos());
+        return StepOutcome::Done;
+      }
+    };
+
+    let room = structure.pos().room_name();
+    if !forced
+      && world.is_mine(room)
+      && is_protected(structure.structure_type())
+    {
+      let reason = format!(
+        "refusing to dismantle our {:?} in {} without the task forcing it",
+        structure.structure_type(),
+        room
+      );
+      return StepOutcome::Failed(reason);
+    }
+
+    *target = Target::Structure(structure);
+    match world.intent(name, Intent::Dismantle(target.clone())) {
+      ReturnCode::Ok => StepOutcome::InProgress,
+      ReturnCode::NotInRange => move_to(world, name, target.pos()),
+      ReturnCode::InvalidTarget => {
+        self.salvage(world, director, target.pos());
+        StepOutcome::Done
+      }
+      code => unexpected(code, format!("dismantling @ {}", target.pos())),
+    }
+  }
+
+  /// Queue up pickups for everything dropped around a spot or under us,
+  /// followed by emptying the ruins left on the spot
+  fn salvage(&mut self, world: &dyn World, director: &Director, pos: Position) {
+    if self.capacity() == 0 {
+      return;
+    }
+    let room = match director.room(pos.room_name()) {
+      Some(room) => room,
+      None => return,
+    };
+    // ruins last longer than dropped resources so they're emptied after
+    for ruin in world.ruins(pos.room_name()) {
+      if ruin.pos() == pos && ruin.store().values().any(|amount| *amount > 0) {
+        let withdraw = Action::Withdraw(None, None);
+        self.task.push_front((withdraw, Target::Ruin(ruin)));
+      }
+    }
+    let here = self.pos();
+    // pushed in reverse so the first to pick up ends up in front
+    for r in room.pickup_order(here).into_iter().rev() {
+      if r.pos().get_range_to(&pos) <= 1 || r.pos() == here {
+        self.task.push_front((Action::Pickup, Target::Resource(r.clone())));
+      }
+    }
+  }
+
+  /// Build a construction site until it's finished or we run out of energy
+  fn build(
+    &mut self,
+    world: &mut dyn World,
+    name: &str,
+    target: &mut Target,
+  ) -> StepOutcome {
+    if !target.same_room(&self.parent) {
+      return move_to(world, name, target.pos());
+    }
+
+    let code = world.intent(name, Intent::Build(target.clone()));
+    let spent = match code {
+      ReturnCode::Ok => self.build_power().min(self.store_of(Energy)),
+      ReturnCode::NotInRange => {
+        let moved = move_to(world, name, target.pos());
+        if moved != StepOutcome::InProgress {
+          return moved;
+        }
+        0
+      }
+      // the site is gone or we have nothing left to build with
+      ReturnCode::InvalidTarget | ReturnCode::NotEnoughResources => {
+        return StepOutcome::Done
+      }
+      code => return unexpected(code, format!("building @ {}", target.pos())),
+    };
+
+    // check if the task is completed
+    if self.store_of(Energy) <= spent {
+      return StepOutcome::Done;
+    }
+    match world.refresh(target) {
+      Some(Target::ConstructionSite(site)) => {
+        if site.progress() + spent >= site.progress_total() {
+          return StepOutcome::Done;
+        }
+        // keep the cached progress in step with the live site
+        *target = Target::ConstructionSite(site);
+        StepOutcome::InProgress
+      }
+      _ => StepOutcome::Done,
+    }
+  }
+
+  /// Repair a structure until it reaches the hits it should have or we run
+  /// out of energy
+  fn repair(
+    &mut self,
+    world: &mut dyn World,
+    director: &Director,
+    name: &str,
+    target: &mut Target,
+  ) -> StepOutcome {
+    if !target.same_room(&self.parent) {
+      return move_to(world, name, target.pos());
+    }
+
+    let code = world.intent(name, Intent::Repair(target.clone()));
+    // each hit repaired costs a hundredth of an energy
+    let (repaired, spent) = match code {
+      ReturnCode::Ok => {
+        let spent = self.active_parts(Work).min(self.store_of(Energy));
+        (spent * REPAIR_POWER, spent)
+      }
+      ReturnCode::NotInRange => {
+        let moved = move_to(world, name, target.pos());
+        if moved != StepOutcome::InProgress {
+          return moved;
+        }
+        (0, 0)
+      }
+      ReturnCode::InvalidTarget | ReturnCode::NotEnoughResources => {
+        return StepOutcome::Done
+      }
+      code => return unexpected(code, format!("repairing @ {}", target.pos())),
+    };
+
+    // check if the task is completed
+    if self.store_of(Energy) <= spent {
+      return StepOutcome::Done;
+    }
+    let room = target.pos().room_name();
+    let level = director.room(room).map(|r| r.level()).unwrap_or(0);
+    let wall_hits = wall_hits(world, level);
+    match world.refresh(target) {
+      Some(Target::Structure(s)) => {
+        if s.hits() + repaired >= s.repair_target(wall_hits) {
+          return StepOutcome::Done;
+        }
+        *target = Target::Structure(s);
+        StepOutcome::InProgress
+      }
+      _ => StepOutcome::Done,
+    }
+  }
+
+  /// Upgrade a controller until we run out of energy.
+  /// Upgraders stand 2 or 3 tiles away so the tiles next to the controller
+  /// stay free for claimers and reservers.
+  fn upgrade(
+    &mut self,
+    world: &mut dyn World,
+    director: &Director,
+    name: &str,
+    target: &mut Target,
+  ) -> StepOutcome {
+    let ctrl = match &*target {
+      Target::Controller(ctrl) => ctrl.clone(),
+      _ => {
+        return StepOutcome::Failed(format!("unable to upgrade {:?}", target))
+      }
+    };
+    if !target.same_room(&self.parent) {
+      return move_to(world, name, target.pos());
+    }
+    let room = ctrl.pos().room_name();
+    if !ctrl.my() {
+      return StepOutcome::Failed(format!("{} isn't ours to upgrade", room));
+    }
+
+    let range = self.pos().get_range_to(&ctrl.pos());
+    if range > 3 || range < 2 {
+      let spot = match self.upgrade_spot(world, director, &ctrl) {
+        Some(spot) => Some(spot),
+        None if range > 3 => Some(ctrl.pos()),
+        // nowhere better to stand so upgrade from here
+        None => None,
+      };
+      if let Some(spot) = spot {
+        let moved = move_to(world, name, spot);
+        if moved != StepOutcome::InProgress {
+          return moved;
+        }
+      }
+    }
+    if range > 3 || ctrl.upgrade_blocked() > 0 {
+      return StepOutcome::InProgress;
+    }
+
+    let energy = self.store_of(Energy);
+    let mut spent = self.upgrade_power().min(energy);
+    if ctrl.level() == 8 {
+      // anything past the cap is wasted so wait for the next tick
+      let budget = upgrade_budget(world.time(), room);
+      if budget == 0 {
+        return StepOutcome::InProgress;
+      }
+      spent = spent.min(budget);
+    }
+
+    match world.intent(name, Intent::UpgradeController(room)) {
+      ReturnCode::Ok => {
+        if ctrl.level() == 8 {
+          spend_upgrade_budget(world.time(), room, spent);
+        }
+      }
+      ReturnCode::NotInRange => {
+        let moved = move_to(world, name, ctrl.pos());
+        if moved != StepOutcome::InProgress {
+          return moved;
+        }
+        spent = 0;
+      }
+      ReturnCode::NotEnoughResources => return StepOutcome::Done,
+      ReturnCode::NotOwner => {
+        return StepOutcome::Failed(format!("{} isn't ours to upgrade", room))
+      }
+      code => return unexpected(code, format!("upgrading {}", room)),
+    }
+
+    // check if the task is completed
+    if energy <= spent {
+      return StepOutcome::Done;
+    }
+    match world.refresh(target) {
+      Some(Target::Controller(ctrl)) if ctrl.my() => {
+        *target = Target::Controller(ctrl);
+        StepOutcome::InProgress
+      }
+      _ => StepOutcome::Done,
+    }
+  }
+
+  /// The closest tile 2 or 3 away from a controller that a creep can stand on
+  fn upgrade_spot(
+    &self,
+    world: &dyn World,
+    director: &Director,
+    ctrl: &ControllerData,
+  ) -> Option<Position> {
+    let room = director.room(ctrl.pos().room_name())?;
+    let center = ctrl.pos();
+    let mut spots = vec![];
+    for y in center.y().saturating_sub(3).max(1)..=(center.y() + 3).min(48) {
+      for x in center.x().saturating_sub(3).max(1)..=(center.x() + 3).min(48) {
+        let pos = Position::new(x, y, center.room_name());
+        let range = pos.get_range_to(&center);
+        if range < 2 || range > 3 {
+          continue;
+        }
+        let taken = room
+          .my_creeps()
+          .iter()
+          .any(|c| c.pos() == pos && c.name() != self.name());
+        if !taken
+          && !room.is_blocked(pos)
+          && world.terrain(pos) != Terrain::Wall
+        {
+          spots.push(pos);
+        }
+      }
+    }
+    let here = self.pos();
+    spots.into_iter().min_by_key(|pos| here.get_range_to(pos))
+  }
+
+  /// Pick up a dropped resource
+  fn pickup(
+    &mut self,
+    world: &mut dyn World,
+    name: &str,
+    target: &mut Target,
+  ) -> StepOutcome {
+    if !target.same_room(&self.parent) {
+      return move_to(world, name, target.pos());
+    }
+    // it's gone or we're full
+    if self.free_capacity() == 0 || world.refresh(target).is_none() {
+      return StepOutcome::Done;
+    }
+
+    match world.intent(name, Intent::Pickup(target.clone())) {
+      ReturnCode::Ok | ReturnCode::Full | ReturnCode::InvalidTarget => {
+        StepOutcome::Done
+      }
+      ReturnCode::NotInRange => move_to(world, name, target.pos()),
+      code => unexpected(code, format!("picking up @ {}", target.pos())),
+    }
+  }
+
+  /// Give a resource to a target, or everything we carry one resource a tick
+  /// when there's no resource
+  fn transfer(
+    &mut self,
+    world: &mut dyn World,
+    name: &str,
+    target: &mut Target,
+    resource: Option<ResourceType>,
+    amount: Option<u32>,
+  ) -> StepOutcome {
+    if !target.same_room(&self.parent) {
+      return move_to(world, name, target.pos());
+    }
+
+    // nothing left to give
+    let r = match resource.or_else(|| self.carried()) {
+      Some(r) if self.store_of(r) > 0 => r,
+      _ => return StepOutcome::Done,
+    };
+    let have = self.store_of(r);
+    let give = amount.map(|a| a.min(have));
+
+    match world.intent(name, Intent::Transfer(target.clone(), r, give)) {
+      // keep emptying out the rest of the cargo
+      ReturnCode::Ok if resource.is_none() && self.used_capacity() > have => {
+        StepOutcome::InProgress
+      }
+      ReturnCode::Ok => StepOutcome::Done,
+      ReturnCode::NotInRange => move_to(world, name, target.pos()),
+      // the target is full, gone or can't hold this resource
+      ReturnCode::Full
+      | ReturnCode::InvalidTarget
+      | ReturnCode::NotEnoughResources
+      | ReturnCode::InvalidArgs => StepOutcome::Done,
+      code => {
+        unexpected(code, format!("transferring {:?} @ {}", r, target.pos()))
+      }
+    }
+  }
+
+  /// Take a resource from a target, or anything it holds when there's no
+  /// resource
+  fn withdraw(
+    &mut self,
+    world: &mut dyn World,
+    name: &str,
+    target: &mut Target,
+    resource: Option<ResourceType>,
+    amount: Option<u32>,
+  ) -> StepOutcome {
+    if !target.same_room(&self.parent) {
+      return move_to(world, name, target.pos());
+    }
+
+    // the target is gone
+    *target = match world.refresh(target) {
+      Some(target) => target,
+      None => return StepOutcome::Done,
+    };
+    let store = match target.store() {
+      Some(store) => store.clone(),
+      None => {
+        let reason = format!("unable to withdraw from {:?}", target);
+        return StepOutcome::Failed(reason);
+      }
+    };
+    let free = self.free_capacity();
+    let r = match resource.or_else(|| first_resource(&store)) {
+      Some(r) => r,
+      None => return StepOutcome::Done,
+    };
+    let available = store.get(&r).cloned().unwrap_or(0);
+    // we're full or the target is empty
+    if free == 0 || available == 0 {
+      return StepOutcome::Done;
+    }
+    let take = amount.map(|a| a.min(available).min(free));
+
+    match world.intent(name, Intent::Withdraw(target.clone(), r, take)) {
+      ReturnCode::Ok => {
+        // keep taking whatever else is there while we have room
+        let rest: u32 = store.values().sum::<u32>() - available;
+        if resource.is_none() && free > available && rest > 0 {
+          StepOutcome::InProgress
+        } else {
+          StepOutcome::Done
+        }
+      }
+      ReturnCode::NotInRange => move_to(world, name, target.pos()),
+      ReturnCode::Full
+      | ReturnCode::InvalidTarget
+      | ReturnCode::NotEnoughResources
+      | ReturnCode::InvalidArgs => StepOutcome::Done,
+      code => {
+        unexpected(code, format!("withdrawing {:?} @ {}", r, target.pos()))
+      }
+    }
+  }
+
+  /// Walk home and have the nearest spawn recycle this creep, emptying it
+  /// into storage first. Without a spawn the creep parks by the controller
+  /// and if the spawn refuses it the creep suicides instead. The creep's
+  /// memory is retired along with it.
+  pub fn recycle(&mut self, world: &mut dyn World, director: &Director) {
+    // power creeps have no body and can't be recycled
+    if self.parts().is_empty() {
+      return;
+    }
+
+    let name = self.name().to_string();
+    let pos = self.pos();
+    let home = home_room(&name).unwrap_or_else(|| pos.room_name());
+    let room = match director.room(home) {
+      Some(room) => room,
+      None => {
+        // we can't see home so just head that way
+        world.intent(&name, Intent::MoveTo(Position::new(25, 25, home)));
+        return;
+      }
+    };
+
+    // drop off anything being carried so it isn't lost
+    if let (Some(r), Some(storage)) =
+      (self.carried(), room.structures(StructureType::Storage).first())
+    {
+      let target = Target::Structure(storage.clone());
+      match world.intent(&name, Intent::Transfer(target, r, None)) {
+        ReturnCode::Ok => return,
+        ReturnCode::NotInRange => {
+          let code = world.intent(&name, Intent::MoveTo(storage.pos()));
+          if code != ReturnCode::NoPath {
+            return;
           }
+          debug!("{} can't reach the storage to drop off {:?}", name, r);
         }
-        Action::Mine => {}
-        Action::Attack => {}
-        Action::AttackController => {}
-        Action::Build => {}
-        Action::Claim => {}
-        Action::Dismantle => {}
-        Action::GenerateSafeMode => {}
-        Action::Heal => {}
-        Action::Pickup => {}
-        Action::Repair => {}
-        Action::Reserve => {}
-        Action::Transfer => {}
-        Action::Withdraw => {}
-        Action::Scout => {}
-        Action::Upgrade => {}
+        // a full storage shouldn't keep the creep from being recycled
+        code => debug!("{} couldn't drop off {:?}: {:?}", name, r, code),
       }
+    }
+
+    let spawn = room
+      .structures(StructureType::Spawn)
+      .iter()
+      .min_by_key(|spawn| pos.get_range_to(&spawn.pos()));
+    if let Some(spawn) = spawn {
+      let target = Target::Structure(spawn.clone());
+      match world.intent(&name, Intent::Recycle(target)) {
+        ReturnCode::Ok => world.forget_creep(&name),
+        ReturnCode::NotInRange => {
+          if world.intent(&name, Intent::MoveTo(spawn.pos()))
+            == ReturnCode::NoPath
+          {
+            warn!("{} can't reach a spawn to be recycled", name);
+            self.suicide(world);
+          }
+        }
+        // the spawn is still spawning this creep
+        ReturnCode::Busy => {}
+        code => {
+          warn!("{} couldn't be recycled: {:?}", name, code);
+          self.suicide(world);
+        }
+      }
+      return;
+    }
+
+    // park out of the way until a spawn is built
+    match room.structures(StructureType::Controller).first() {
+      Some(ctrl) => {
+        if pos.get_range_to(&ctrl.pos()) > 3
+          && world.intent(&name, Intent::MoveTo(ctrl.pos()))
+            == ReturnCode::NoPath
+        {
+          self.suicide(world);
+        }
+      }
+      None => self.suicide(world),
+    }
+  }
+
+  /// Kill this creep and retire its memory
+  fn suicide(&self, world: &mut dyn World) {
+    if world.intent(self.name(), Intent::Suicide) == ReturnCode::Ok {
+      world.forget_creep(self.name());
+    }
+  }
+}
+
+/// Forget what's kept on the heap for creeps that are no longer alive
+pub fn forget_dead_creeps(alive: &HashSet<String>) {
+  SCOUTING.with(|s| s.borrow_mut().retain(|name, _| alive.contains(name)));
+  RETRIES.with(|r| r.borrow_mut().retain(|name, _| alive.contains(name)));
+}
+
+/// Move toward a position as part of a step
+fn move_to(world: &mut dyn World, name: &str, pos: Position) -> StepOutcome {
+  match world.intent(name, Intent::MoveTo(pos)) {
+    ReturnCode::Ok => StepOutcome::InProgress,
+    code => unexpected(code, format!("moving to {}", pos)),
+  }
+}
+
+/// What a return code a step didn't plan for means for it
+fn unexpected(code: ReturnCode, doing: String) -> StepOutcome {
+  match code {
+    // still spawning or too tired to move
+    ReturnCode::Busy | ReturnCode::Tired => StepOutcome::InProgress,
+    // something is standing in the way
+    ReturnCode::NoPath => StepOutcome::Blocked,
+    code => StepOutcome::Failed(format!("{} returned {:?}", doing, code)),
+  }
+}
+
+/// The ticks to wait before retrying a step that's been blocked this many
+/// times
+fn backoff(attempts: u32) -> u32 {
+  1 << attempts.min(6)
+}
+
+/// The energy left to spend upgrading a max level controller this tick
+fn upgrade_budget(time: u32, room: RoomName) -> u32 {
+  UPGRADED.with(|upgraded| {
+    let upgraded = upgraded.borrow();
+    let spent = if upgraded.0 == time {
+      upgraded.1.get(&room).cloned().unwrap_or(0)
     } else {
-      self.recycle();
+      0
+    };
+    CONTROLLER_MAX_UPGRADE_PER_TICK.saturating_sub(spent)
+  })
+}
+
+/// Record energy spent upgrading a max level controller this tick
+fn spend_upgrade_budget(time: u32, room: RoomName, amount: u32) {
+  UPGRADED.with(|upgraded| {
+    let mut upgraded = upgraded.borrow_mut();
+    if upgraded.0 != time {
+      *upgraded = (time, HashMap::new());
+    }
+    *upgraded.1.entry(room).or_insert(0) += amount;
+  })
+}
+
+/// Where a static miner should stand. That's a container next to the source
+/// or the open tile next to it that's closest to a spawn.
+fn mining_spot(
+  world: &dyn World,
+  room: &RoomData,
+  source: Position,
+) -> Option<Position> {
+  let next_to = |pos: &Position| pos.get_range_to(&source) == 1;
+  if let Some(container) =
+    room.structures(StructureType::Container).iter().find(|c| next_to(&c.pos()))
+  {
+    return Some(container.pos());
+  }
+
+  let spawn = room.structures(StructureType::Spawn).first().map(|s| s.pos());
+  let mut spots = vec![];
+  for y in source.y().saturating_sub(1).max(1)..=(source.y() + 1).min(48) {
+    for x in source.x().saturating_sub(1).max(1)..=(source.x() + 1).min(48) {
+      let pos = Position::new(x, y, source.room_name());
+      if next_to(&pos)
+        && !room.is_blocked(pos)
+        && world.terrain(pos) != Terrain::Wall
+      {
+        spots.push(pos);
+      }
+    }
+  }
+  spots.into_iter().min_by_key(|pos| match spawn {
+    Some(spawn) => pos.get_range_to(&spawn),
+    None => 0,
+  })
+}
+
+/// A link or container a miner standing here can empty into, preferring links
+fn drop_off(room: &RoomData, spot: Position) -> Option<&StructureData> {
+  let in_reach = |s: &&StructureData| s.pos().get_range_to(&spot) <= 1;
+  room
+    .structures(StructureType::Link)
+    .iter()
+    .find(in_reach)
+    .or_else(|| room.structures(StructureType::Container).iter().find(in_reach))
+}
+
+/// Returns true for structures we never dismantle in our own rooms unless
+/// the task forces it
+fn is_protected(structure_type: StructureType) -> bool {
+  matches!(
+    structure_type,
+    StructureType::Spawn | StructureType::Storage | StructureType::Terminal
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn room() -> RoomName {
+    RoomName::new("W1N1").unwrap()
+  }
+
+  /// A room with one creep of ours and nowhere to recycle it, so worn out
+  /// creeps are told to suicide
+  fn world() -> (MockWorld, Director) {
+    let mut world = MockWorld::new("Player");
+    let mut data = RoomData::new(room(), 1);
+    let pos = Position::new(25, 25, room());
+    let body = [Move, Carry];
+    data.add_creep(CommonCreepData::new("Jim", mock_id(1), pos, &body));
+    world.add_room(MockRoom::new(data, true));
+    let director = Director::new(&world);
+    (world, director)
+  }
+
+  fn jim(world: &MockWorld) -> MyCreepData {
+    world.my_creeps().remove(0)
+  }
+
+  #[test]
+  fn creeps_never_given_a_task_are_kept() {
+    let (mut world, mut director) = world();
+    world.time = 10_000;
+    world.creep_mut("Jim").unwrap().set_ticks_to_live(1);
+    jim(&world).run(&mut world, &mut director);
+    assert!(world.intents.is_empty());
+  }
+
+  #[test]
+  fn idle_creeps_are_recycled_after_a_while() {
+    let (mut world, mut director) = world();
+    world.creep_idle.insert("Jim".to_string(), 0);
+    world.time = idle_ticks(&world) - 1;
+    jim(&world).run(&mut world, &mut director);
+    assert!(world.intents.is_empty());
+
+    world.time += 1;
+    jim(&world).run(&mut world, &mut director);
+    assert_eq!(world.intents, vec![("Jim".to_string(), Intent::Suicide)]);
+  }
+
+  #[test]
+  fn idle_creeps_about_to_die_are_recycled() {
+    let (mut world, mut director) = world();
+    world.creep_idle.insert("Jim".to_string(), 0);
+    world.time = 1;
+    world.creep_mut("Jim").unwrap().set_ticks_to_live(1);
+    jim(&world).run(&mut world, &mut director);
+    assert_eq!(world.intents, vec![("Jim".to_string(), Intent::Suicide)]);
+  }
+
+  #[test]
+  fn a_new_task_stops_the_idle_clock() {
+    let (mut world, _) = world();
+    world.creep_idle.insert("Jim".to_string(), 0);
+    let mut creep = jim(&world);
+    assert_eq!(creep.idle_since(), Some(0));
+    creep.assign(Task::default());
+    assert_eq!(creep.idle_since(), None);
+  }
+
+  /// A pile of energy next to Jim, or one a room over
+  fn pile(world: &mut MockWorld, here: bool) -> Target {
+    let name = if here { room() } else { RoomName::new("W2N1").unwrap() };
+    let pos = Position::new(26, 25, name);
+    let pile = ResourceData::new(pos, mock_id(2), Energy, 100);
+    if let Some(room) = world.rooms.get_mut(&name) {
+      room.data.add_resource(pile.clone());
     }
+    Target::Resource(pile)
   }
 
-  /// Recycle this creep
-  pub fn recycle(&self) -> Self {
-    todo!()
+  /// Have Jim pick up a pile with every intent answered by `reply`
+  fn pickup(reply: ReturnCode, here: bool) -> StepOutcome {
+    let (mut world, _) = world();
+    let mut target = pile(&mut world, here);
+    world.reply = reply;
+    jim(&world).pickup(&mut world, "Jim", &mut target)
+  }
+
+  #[test]
+  fn steps_in_another_room_are_in_progress() {
+    assert_eq!(pickup(ReturnCode::Ok, false), StepOutcome::InProgress);
+    assert_eq!(pickup(ReturnCode::Tired, true), StepOutcome::InProgress);
+  }
+
+  #[test]
+  fn steps_the_game_accepts_are_done() {
+    assert_eq!(pickup(ReturnCode::Ok, true), StepOutcome::Done);
+  }
+
+  #[test]
+  fn steps_the_game_refuses_fail() {
+    let outcome = pickup(ReturnCode::NotOwner, true);
+    assert!(matches!(outcome, StepOutcome::Failed(_)), "{:?}", outcome);
+  }
+
+  #[test]
+  fn steps_without_a_path_are_blocked() {
+    assert_eq!(pickup(ReturnCode::NoPath, true), StepOutcome::Blocked);
+  }
+
+  #[test]
+  fn blocked_steps_are_kept_for_later() {
+    let (mut world, mut director) = world();
+    let mut task = Task::default();
+    task.push_back((Action::Pickup, pile(&mut world, true)));
+    world.reply = ReturnCode::NoPath;
+    let mut creep = jim(&world);
+    creep.assign(task.clone());
+
+    creep.run(&mut world, &mut director);
+    assert_eq!(creep.task(), &task);
+    // it waits out the backoff before trying again
+    world.intents.clear();
+    world.time += 1;
+    creep.run(&mut world, &mut director);
+    assert!(world.intents.is_empty());
+  }
+
+  #[test]
+  fn failed_steps_give_the_task_back() {
+    let (mut world, mut director) = world();
+    let mut task = Task::default();
+    task.push_back((Action::Pickup, pile(&mut world, true)));
+    world.reply = ReturnCode::NotOwner;
+    let mut creep = jim(&world);
+    creep.assign(task);
+
+    creep.run(&mut world, &mut director);
+    assert!(creep.task().is_empty());
+    assert_eq!(creep.idle_since(), Some(world.time));
+  }
+
+  #[test]
+  fn scouts_carry_on_after_a_reset() {
+    let (mut world, mut director) = world();
+    let pos = |x| Position::new(x, 25, room());
+    let mut creep = jim(&world);
+    let mut task = Task::default();
+    task.push_back((Action::Scout, Target::Path([pos(25), pos(30)])));
+    creep.assign(task);
+
+    for x in 26..=28 {
+      creep.run(&mut world, &mut director);
+      world.creep_mut("Jim").unwrap().set_pos(pos(x));
+    }
+    // the VM reset so only the task is left
+    SCOUTING.with(|s| s.borrow_mut().clear());
+    world.intents.clear();
+    creep.run(&mut world, &mut director);
+    assert_eq!(
+      world.intents,
+      vec![("Jim".to_string(), Intent::MoveTo(pos(29)))]
+    );
+
+    world.creep_mut("Jim").unwrap().set_pos(pos(29));
+    creep.run(&mut world, &mut director);
+    assert!(creep.task().is_empty());
+  }
+
+  #[test]
+  fn dismantled_structures_are_salvaged() {
+    let (mut world, _) = world();
+    let spot = Position::new(26, 25, room());
+    let pile = pile(&mut world, true);
+    let mut store = HashMap::new();
+    store.insert(Energy, 500);
+    let ruin = RuinData::new(spot, mock_id(3), store);
+    world.ruins.push(ruin.clone());
+    let director = Director::new(&world);
+
+    // the wall is already gone
+    let wall = StructureData::new(spot, StructureType::Wall);
+    let mut target = Target::Structure(wall);
+    let mut creep = jim(&world);
+    let outcome =
+      creep.dismantle(&mut world, &director, "Jim", false, &mut target);
+    assert_eq!(outcome, StepOutcome::Done);
+
+    let mut salvage = Task::default();
+    salvage.push_back((Action::Pickup, pile));
+    salvage.push_back((Action::Withdraw(None, None), Target::Ruin(ruin)));
+    assert_eq!(creep.task(), &salvage);
   }
 }
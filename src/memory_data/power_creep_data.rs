@@ -0,0 +1,106 @@
+use crate::*;
+
+/// The level and cooldown of a power one of our power creeps has
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone, Copy)]
+pub struct PowerSkill {
+  /// The level the power has been upgraded to
+  pub level: u8,
+  /// The ticks before the power can be used again
+  pub cooldown: u32,
+}
+
+/// What the account knows about one of our power creeps, spawned or not
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct PowerCreepData {
+  name: String,
+  level: u32,
+  spawned: bool,
+  powers: HashMap<PowerType, PowerSkill>,
+}
+
+impl Display for PowerCreepData {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    writeln!(f, "{}: level {}", self.name, self.level)?;
+    for (power, skill) in &self.powers {
+      writeln!(
+        f,
+        "{:?} {} on cooldown {}",
+        power, skill.level, skill.cooldown
+      )?;
+    }
+
+    Ok(())
+  }
+}
+
+impl From<AccountPowerCreep> for PowerCreepData {
+  fn from(c: AccountPowerCreep) -> Self {
+    let powers = c
+      .powers()
+      .into_iter()
+      .map(|(power, info)| {
+        let skill =
+          PowerSkill { level: info.level as u8, cooldown: info.cooldown };
+        (power, skill)
+      })
+      .collect();
+
+    PowerCreepData {
+      name: c.name(),
+      level: c.level(),
+      spawned: c.shard().is_some(),
+      powers,
+    }
+  }
+}
+
+impl PowerCreepData {
+  /// Get the name of the power creep
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  /// Get the level of the power creep
+  pub fn level(&self) -> u32 {
+    self.level
+  }
+
+  /// Returns true if the power creep is alive on any shard
+  pub fn spawned(&self) -> bool {
+    self.spawned
+  }
+
+  /// Get the powers the power creep has
+  pub fn powers(&self) -> &HashMap<PowerType, PowerSkill> {
+    &self.powers
+  }
+
+  /// Returns true if the power creep has the power and it's off cooldown
+  pub fn can_use(&self, power: PowerType) -> bool {
+    self.powers.get(&power).map_or(false, |skill| skill.cooldown == 0)
+  }
+}
+
+/// Power creeps for tests, with their powers picked out by hand
+#[cfg(any(test, feature = "native"))]
+impl PowerCreepData {
+  /// Build power creep data
+  pub fn new(name: &str, level: u32, spawned: bool) -> Self {
+    PowerCreepData {
+      name: name.to_string(),
+      level,
+      spawned,
+      powers: HashMap::new(),
+    }
+  }
+
+  /// Give the power creep a power
+  pub fn set_power(&mut self, power: PowerType, skill: PowerSkill) {
+    self.powers.insert(power, skill);
+  }
+
+  /// Mark the power creep as spawned or not
+  pub fn set_spawned(&mut self, spawned: bool) {
+    self.spawned = spawned;
+  }
+}
@@ -0,0 +1,77 @@
+use crate::*;
+
+/// This serializes a dropped resource
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct ResourceData {
+  pos: CommonData,
+  id: ObjectId<Resource>,
+  resource_type: ResourceType,
+  amount: u32,
+}
+
+impl HasPosition for ResourceData {
+  fn pos(&self) -> Position {
+    self.pos.pos()
+  }
+}
+
+impl Display for ResourceData {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    writeln!(
+      f,
+      "{} {:?} dropped @ {}",
+      self.amount, self.resource_type, self.pos
+    )
+  }
+}
+
+impl From<Resource> for ResourceData {
+  fn from(resource: Resource) -> Self {
+    let id = resource.id();
+    let resource_type = resource.resource_type();
+    let amount = resource.amount();
+    let pos = resource.pos().into();
+    ResourceData { pos, id, resource_type, amount }
+  }
+}
+
+impl ResourceData {
+  /// Get the id of the resource
+  pub fn id(&self) -> ObjectId<Resource> {
+    self.id
+  }
+
+  /// Get the type of resource
+  pub fn resource_type(&self) -> ResourceType {
+    self.resource_type
+  }
+
+  /// Get the amount on the ground
+  pub fn amount(&self) -> u32 {
+    self.amount
+  }
+
+  /// The amount lost each tick it's left on the ground
+  pub fn decay(&self) -> u32 {
+    (self.amount + ENERGY_DECAY - 1) / ENERGY_DECAY
+  }
+}
+
+/// Dropped resources the simulator leaves behind and decays
+#[cfg(any(test, feature = "native"))]
+impl ResourceData {
+  /// Build resource data
+  pub fn new(
+    pos: Position,
+    id: ObjectId<Resource>,
+    resource_type: ResourceType,
+    amount: u32,
+  ) -> Self {
+    ResourceData { pos: pos.into(), id, resource_type, amount }
+  }
+
+  /// Change the amount on the ground
+  pub fn set_amount(&mut self, amount: u32) {
+    self.amount = amount;
+  }
+}
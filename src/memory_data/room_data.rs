@@ -1,7 +1,8 @@
 use crate::*;
 
 /// The AgentCell is a single room and manages all of the info for that cell
-#[derive(Serialize, Deserialize, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+#[serde(remote = "Self")]
 pub struct RoomData {
   name: RoomName,
   level: u8,
@@ -13,6 +14,84 @@ pub struct RoomData {
   my_creeps: Vec<CommonCreepData>,
   my_power_creeps: Vec<CommonCreepData>,
   other_creeps: Vec<CommonCreepData>,
+  controller: Option<ControllerData>,
+  resources: Vec<ResourceData>,
+}
+
+crate::layouts!(RoomData {
+  4 => RoomDataV3,
+  6 => RoomDataV5,
+});
+
+/// The layout before controllers were tracked
+#[derive(Deserialize)]
+struct RoomDataV3 {
+  name: RoomName,
+  level: u8,
+  construction: HashMap<StructureType, Vec<ConstructionData>>,
+  structures: HashMap<StructureType, Vec<StructureData>>,
+  sources: Vec<SourceData>,
+  mineral: Option<MineralData>,
+  deposit: Option<DepositData>,
+  my_creeps: Vec<CommonCreepData>,
+  my_power_creeps: Vec<CommonCreepData>,
+  other_creeps: Vec<CommonCreepData>,
+}
+
+impl From<RoomDataV3> for RoomData {
+  fn from(old: RoomDataV3) -> Self {
+    // This gets filled in the next time the room is seen.
+    RoomData {
+      name: old.name,
+      level: old.level,
+      construction: old.construction,
+      structures: old.structures,
+      sources: old.sources,
+      mineral: old.mineral,
+      deposit: old.deposit,
+      my_creeps: old.my_creeps,
+      my_power_creeps: old.my_power_creeps,
+      other_creeps: old.other_creeps,
+      controller: None,
+      resources: vec![],
+    }
+  }
+}
+
+/// The layout before dropped resources were tracked
+#[derive(Deserialize)]
+struct RoomDataV5 {
+  name: RoomName,
+  level: u8,
+  construction: HashMap<StructureType, Vec<ConstructionData>>,
+  structures: HashMap<StructureType, Vec<StructureData>>,
+  sources: Vec<SourceData>,
+  mineral: Option<MineralData>,
+  deposit: Option<DepositData>,
+  my_creeps: Vec<CommonCreepData>,
+  my_power_creeps: Vec<CommonCreepData>,
+  other_creeps: Vec<CommonCreepData>,
+  controller: Option<ControllerData>,
+}
+
+impl From<RoomDataV5> for RoomData {
+  fn from(old: RoomDataV5) -> Self {
+    // These get filled in the next time the room is seen.
+    RoomData {
+      name: old.name,
+      level: old.level,
+      construction: old.construction,
+      structures: old.structures,
+      sources: old.sources,
+      mineral: old.mineral,
+      deposit: old.deposit,
+      my_creeps: old.my_creeps,
+      my_power_creeps: old.my_power_creeps,
+      other_creeps: old.other_creeps,
+      controller: old.controller,
+      resources: vec![],
+    }
+  }
 }
 
 impl Display for RoomData {
@@ -44,12 +123,8 @@ impl From<Room> for RoomData {
     let name = room.name();
 
     // determine the level of the room
-    let level =
-      if let Some(ctrl) = room.controller() as Option<StructureController> {
-        ctrl.level() as u8
-      } else {
-        0
-      };
+    let controller: Option<ControllerData> = room.controller().map(Into::into);
+    let level = controller.as_ref().map(|c| c.level()).unwrap_or(0);
 
     // initialize collection variables
     let mut construction: HashMap<StructureType, Vec<ConstructionData>> =
@@ -98,14 +173,25 @@ impl From<Room> for RoomData {
       sources.push(s.into());
     }
 
+    let resources =
+      room.find(find::DROPPED_RESOURCES).into_iter().map(Into::into).collect();
+
     for site in room.find(find::CONSTRUCTION_SITES) {
       let entry = construction.entry(site.structure_type()).or_default();
       entry.push(site.into());
     }
 
-    for s in room.find(find::MY_STRUCTURES) {
+    // containers, roads and walls belong to nobody so they aren't among our
+    // structures
+    let unowned = room
+      .find(find::STRUCTURES)
+      .into_iter()
+      .filter(|s| is_unowned(s.structure_type()));
+    let mine =
+      room.find(find::MY_STRUCTURES).into_iter().map(|s| s.as_structure());
+    for s in mine.chain(unowned) {
       let entry = structures.entry(s.structure_type()).or_default();
-      entry.push(s.as_structure().into());
+      entry.push(s.into());
     }
 
     RoomData {
@@ -119,6 +205,8 @@ impl From<Room> for RoomData {
       my_creeps,
       my_power_creeps,
       other_creeps,
+      controller,
+      resources,
     }
   }
 }
@@ -128,4 +216,330 @@ impl RoomData {
   pub fn is_visible(&self) -> bool {
     game::rooms::get(self.name).is_some()
   }
+
+  /// Get the name of the room
+  pub fn name(&self) -> RoomName {
+    self.name
+  }
+
+  /// Get the controller level of the room
+  pub fn level(&self) -> u8 {
+    self.level
+  }
+
+  /// Get the sources in the room
+  pub fn sources(&self) -> &Vec<SourceData> {
+    &self.sources
+  }
+
+  /// Get all the structures of a type
+  pub fn structures(&self, structure_type: StructureType) -> &[StructureData] {
+    self.structures.get(&structure_type).map(Vec::as_slice).unwrap_or(&[])
+  }
+
+  /// Count the structures of each type, named the way `Debug` names them
+  pub fn structure_counts(&self) -> BTreeMap<String, usize> {
+    self
+      .structures
+      .iter()
+      .filter(|(_, structures)| !structures.is_empty())
+      .map(|(t, structures)| (format!("{:?}", t), structures.len()))
+      .collect()
+  }
+
+  /// Get the controller
+  pub fn controller(&self) -> Option<&ControllerData> {
+    self.controller.as_ref()
+  }
+
+  /// Get the player holding a reservation on this room along with the ticks
+  /// left on it as of `time`
+  pub fn reservation(&self, time: u32) -> Option<(&str, u32)> {
+    let ctrl = self.controller.as_ref()?;
+    let ticks = ctrl.reservation_ticks(time);
+    ctrl.reserved_by().filter(|_| ticks > 0).map(|name| (name, ticks))
+  }
+
+  /// Get the dropped resources
+  pub fn resources(&self) -> &Vec<ResourceData> {
+    &self.resources
+  }
+
+  /// Dropped resources in the order they should be picked up.
+  /// The ones losing the most each tick come first and ties go to the
+  /// closest.
+  pub fn pickup_order(&self, from: Position) -> Vec<&ResourceData> {
+    let mut resources: Vec<&ResourceData> = self.resources.iter().collect();
+    resources.sort_by_key(|r| {
+      (std::cmp::Reverse(r.decay()), from.get_range_to(&r.pos()))
+    });
+    resources
+  }
+
+  /// Get all the construction sites
+  pub fn construction(&self) -> impl Iterator<Item = &ConstructionData> {
+    self.construction.values().flatten()
+  }
+
+  /// Get all of our creeps in the room
+  pub fn my_creeps(&self) -> &Vec<CommonCreepData> {
+    &self.my_creeps
+  }
+
+  /// Get all of our spawned power creeps in the room
+  pub fn my_power_creeps(&self) -> &Vec<CommonCreepData> {
+    &self.my_power_creeps
+  }
+
+  /// Get all of the hostile creeps in the room
+  pub fn other_creeps(&self) -> &Vec<CommonCreepData> {
+    &self.other_creeps
+  }
+
+  /// Returns true if a structure, source or mineral stops creeps from
+  /// standing on this tile. Terrain isn't checked.
+  pub fn is_blocked(&self, pos: Position) -> bool {
+    self.sources.iter().any(|s| s.pos() == pos)
+      || self.mineral.iter().any(|m| m.pos() == pos)
+      || self
+        .structures
+        .iter()
+        .filter(|(t, _)| is_obstacle(**t))
+        .any(|(_, structures)| structures.iter().any(|s| s.pos() == pos))
+  }
+
+  /// Find the current version of a target in this room
+  pub fn refresh(&self, target: &Target) -> Option<Target> {
+    match target {
+      Target::Source(s) => self
+        .sources
+        .iter()
+        .find(|o| o.id() == s.id())
+        .map(|o| Target::Source(o.clone())),
+      Target::Mineral(m) => self
+        .mineral
+        .iter()
+        .find(|o| o.id() == m.id())
+        .map(|o| Target::Mineral(o.clone())),
+      Target::Deposit(d) => self
+        .deposit
+        .iter()
+        .find(|o| o.id() == d.id())
+        .map(|o| Target::Deposit(o.clone())),
+      Target::ConstructionSite(c) => self
+        .construction()
+        .find(|o| o.id() == c.id())
+        .map(|o| Target::ConstructionSite(o.clone())),
+      Target::Structure(s) => self
+        .structures(s.structure_type())
+        .iter()
+        .find(|o| o.pos() == s.pos())
+        .map(|o| Target::Structure(o.clone())),
+      Target::Creep(c) => self
+        .my_creeps
+        .iter()
+        .chain(self.my_power_creeps.iter())
+        .chain(self.other_creeps.iter())
+        .find(|o| o.id() == c.id())
+        .map(|o| Target::Creep(o.clone())),
+      Target::Controller(c) => self
+        .controller
+        .iter()
+        .find(|o| o.id() == c.id())
+        .map(|o| Target::Controller(o.clone())),
+      Target::Resource(r) => self
+        .resources
+        .iter()
+        .find(|o| o.id() == r.id())
+        .map(|o| Target::Resource(o.clone())),
+      Target::Path(_) => Some(target.clone()),
+      // tombstones and ruins aren't tracked by rooms
+      Target::Tombstone(_) | Target::Ruin(_) => None,
+    }
+  }
+
+  /// Returns true if anything that outlasts a tick differs from an older
+  /// snapshot of the room. Creeps, dropped resources, stores, hits and timers
+  /// change every tick and are refreshed whenever the room is seen, so they're
+  /// left out.
+  pub fn has_changed(&self, old: &RoomData) -> bool {
+    self.outline() != old.outline()
+  }
+
+  /// Everything in the room that outlasts a tick
+  fn outline(&self) -> RoomOutline {
+    RoomOutline {
+      level: self.level,
+      construction: self.construction().map(|c| c.id()).collect(),
+      structures: self
+        .structures
+        .iter()
+        .flat_map(|(t, s)| s.iter().map(move |s| (*t, s.pos())))
+        .collect(),
+      sources: self.sources.iter().map(|s| s.id()).collect(),
+      mineral: self.mineral.as_ref().map(|m| m.id()),
+      deposit: self.deposit.as_ref().map(|d| d.id()),
+      controller: self.controller.as_ref().map(|c| {
+        (
+          c.my(),
+          c.owner().map(String::from),
+          c.reserved_by().map(String::from),
+          c.safe_mode_available(),
+          c.power_enabled(),
+        )
+      }),
+    }
+  }
+}
+
+/// Rooms for the mock world, filled in a piece at a time
+#[cfg(any(test, feature = "native"))]
+impl RoomData {
+  /// Build an empty room
+  pub fn new(name: RoomName, level: u8) -> Self {
+    RoomData {
+      name,
+      level,
+      construction: HashMap::new(),
+      structures: HashMap::new(),
+      sources: vec![],
+      mineral: None,
+      deposit: None,
+      my_creeps: vec![],
+      my_power_creeps: vec![],
+      other_creeps: vec![],
+      controller: None,
+      resources: vec![],
+    }
+  }
+
+  /// Add a source
+  pub fn add_source(&mut self, source: SourceData) {
+    self.sources.push(source);
+  }
+
+  /// Add a structure
+  pub fn add_structure(&mut self, structure: StructureData) {
+    let entry = self.structures.entry(structure.structure_type()).or_default();
+    entry.push(structure);
+  }
+
+  /// Add a construction site
+  pub fn add_construction(&mut self, site: ConstructionData) {
+    let entry = self.construction.entry(site.structure_type()).or_default();
+    entry.push(site);
+  }
+
+  /// Add one of our creeps
+  pub fn add_creep(&mut self, creep: CommonCreepData) {
+    self.my_creeps.push(creep);
+  }
+
+  /// Add one of our power creeps
+  pub fn add_power_creep(&mut self, creep: CommonCreepData) {
+    self.my_power_creeps.push(creep);
+  }
+
+  /// Add a hostile creep
+  pub fn add_hostile(&mut self, creep: CommonCreepData) {
+    self.other_creeps.push(creep);
+  }
+
+  /// Find a structure by position and type
+  pub fn structure_mut(
+    &mut self,
+    pos: Position,
+    structure_type: StructureType,
+  ) -> Option<&mut StructureData> {
+    self
+      .structures
+      .get_mut(&structure_type)?
+      .iter_mut()
+      .find(|s| s.pos() == pos)
+  }
+
+  /// Find a construction site by id
+  pub fn construction_mut(
+    &mut self,
+    id: ObjectId<ConstructionSite>,
+  ) -> Option<&mut ConstructionData> {
+    self.construction.values_mut().flatten().find(|c| c.id() == id)
+  }
+
+  /// Remove a construction site
+  pub fn remove_construction(
+    &mut self,
+    id: ObjectId<ConstructionSite>,
+  ) -> Option<ConstructionData> {
+    for sites in self.construction.values_mut() {
+      if let Some(i) = sites.iter().position(|c| c.id() == id) {
+        return Some(sites.remove(i));
+      }
+    }
+    None
+  }
+
+  /// Drop a resource
+  pub fn add_resource(&mut self, resource: ResourceData) {
+    self.resources.push(resource);
+  }
+
+  /// Get mutable access to the dropped resources
+  pub fn resources_mut(&mut self) -> &mut Vec<ResourceData> {
+    &mut self.resources
+  }
+
+  /// Get mutable access to the sources
+  pub fn sources_mut(&mut self) -> &mut Vec<SourceData> {
+    &mut self.sources
+  }
+
+  /// Get mutable access to our creeps
+  pub fn my_creeps_mut(&mut self) -> &mut Vec<CommonCreepData> {
+    &mut self.my_creeps
+  }
+
+  /// Change the controller level
+  pub fn set_level(&mut self, level: u8) {
+    self.level = level;
+  }
+
+  /// Set the controller
+  pub fn set_controller(&mut self, controller: ControllerData) {
+    self.level = controller.level();
+    self.controller = Some(controller);
+  }
+
+  /// Get mutable access to the controller
+  pub fn controller_mut(&mut self) -> Option<&mut ControllerData> {
+    self.controller.as_mut()
+  }
+}
+
+/// The parts of a room that are worth saving again when they change
+#[derive(PartialEq)]
+struct RoomOutline {
+  level: u8,
+  construction: HashSet<ObjectId<ConstructionSite>>,
+  structures: HashSet<(StructureType, Position)>,
+  sources: Vec<ObjectId<Source>>,
+  mineral: Option<ObjectId<Mineral>>,
+  deposit: Option<ObjectId<Deposit>>,
+  controller: Option<(bool, Option<String>, Option<String>, u32, bool)>,
+}
+
+/// Returns true if this type of structure is never owned by a player
+pub fn is_unowned(structure_type: StructureType) -> bool {
+  matches!(
+    structure_type,
+    StructureType::Container | StructureType::Road | StructureType::Wall
+  )
+}
+
+/// Returns true if creeps can't walk through this type of structure
+pub fn is_obstacle(structure_type: StructureType) -> bool {
+  !matches!(
+    structure_type,
+    StructureType::Road | StructureType::Container | StructureType::Rampart
+  )
 }
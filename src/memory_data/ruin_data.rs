@@ -1,7 +1,7 @@
 use crate::*;
 
 /// This serializes all the info about a structure
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 pub struct RuinData {
   pos: CommonData,
   id: ObjectId<Ruin>,
@@ -39,3 +39,28 @@ impl From<Ruin> for RuinData {
     RuinData { pos, id, resources }
   }
 }
+
+impl RuinData {
+  /// Get the id of the ruin
+  pub fn id(&self) -> ObjectId<Ruin> {
+    self.id
+  }
+
+  /// Get everything left in the ruin
+  pub fn store(&self) -> &HashMap<ResourceType, u32> {
+    &self.resources
+  }
+}
+
+/// Ruins for the mock world, which rooms don't keep track of
+#[cfg(any(test, feature = "native"))]
+impl RuinData {
+  /// Build ruin data
+  pub fn new(
+    pos: Position,
+    id: ObjectId<Ruin>,
+    resources: HashMap<ResourceType, u32>,
+  ) -> Self {
+    RuinData { pos: pos.into(), id, resources }
+  }
+}
@@ -1,7 +1,7 @@
 use crate::*;
 
 /// This serializes source data
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 pub struct SourceData {
   pos: CommonData,
   id: ObjectId<Source>,
@@ -35,6 +35,42 @@ impl From<Source> for SourceData {
   }
 }
 
+impl SourceData {
+  /// Get the id of the source
+  pub fn id(&self) -> ObjectId<Source> {
+    self.id
+  }
+
+  /// Get the energy left in the source
+  pub fn amount(&self) -> u32 {
+    self.amount
+  }
+
+  /// Get the most energy the source can hold
+  pub fn capacity(&self) -> u32 {
+    self.capacity
+  }
+}
+
+/// Sources for fixtures, which the simulator drains and refills
+#[cfg(any(test, feature = "native"))]
+impl SourceData {
+  /// Build source data
+  pub fn new(
+    pos: Position,
+    id: ObjectId<Source>,
+    amount: u32,
+    capacity: u32,
+  ) -> Self {
+    SourceData { pos: pos.into(), id, amount, capacity }
+  }
+
+  /// Change the energy left in the source
+  pub fn set_amount(&mut self, amount: u32) {
+    self.amount = amount.min(self.capacity);
+  }
+}
+
 /// unwrap
 impl SourceData {
   /// unwrap the source that this refers to
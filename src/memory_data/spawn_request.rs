@@ -0,0 +1,15 @@
+use crate::*;
+
+/// A creep the Director wants spawned for a task no creep can take.
+/// `spawn_requested` spawns them in the room they name.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SpawnRequest {
+  /// The owned room closest to where the task starts
+  pub room: RoomName,
+  /// The parts the creep needs, at least one from each group
+  pub parts: Vec<HashSet<Part>>,
+  /// The least the creep has to be able to carry
+  pub capacity: u32,
+  /// The task the creep is wanted for
+  pub task: Task,
+}
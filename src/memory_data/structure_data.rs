@@ -1,11 +1,39 @@
 use crate::*;
 
 /// This serializes all the info about a structure
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+#[serde(remote = "Self")]
 pub struct StructureData {
   pos: CommonData,
   structure_type: StructureType,
   resources: HashMap<ResourceType, u32>,
+  hits: u32,
+  max_hits: u32,
+}
+
+crate::layouts!(StructureData {
+  3 => StructureDataV2,
+});
+
+/// The layout before hits were tracked
+#[derive(Deserialize)]
+struct StructureDataV2 {
+  pos: CommonData,
+  structure_type: StructureType,
+  resources: HashMap<ResourceType, u32>,
+}
+
+impl From<StructureDataV2> for StructureData {
+  fn from(old: StructureDataV2) -> Self {
+    // These get filled in the next time the structure is seen.
+    StructureData {
+      pos: old.pos,
+      structure_type: old.structure_type,
+      resources: old.resources,
+      hits: 0,
+      max_hits: 0,
+    }
+  }
 }
 
 impl HasPosition for StructureData {
@@ -17,6 +45,9 @@ impl HasPosition for StructureData {
 impl Display for StructureData {
   fn fmt(&self, f: &mut Formatter<'_>) -> Result {
     writeln!(f, "{:?} @ {}", self.structure_type, self.pos,)?;
+    if self.max_hits > 0 {
+      writeln!(f, "{} of {} HP", self.hits, self.max_hits)?;
+    }
 
     for (r, amount) in &self.resources {
       writeln!(f, "{:?}: {}", r, amount)?;
@@ -38,10 +69,58 @@ impl From<Structure> for StructureData {
       }
     }
 
-    StructureData { structure_type, pos, resources }
+    let (hits, max_hits) = match s.as_attackable() {
+      Some(a) => (a.hits(), a.hits_max()),
+      None => (0, 0),
+    };
+
+    StructureData { structure_type, pos, resources, hits, max_hits }
   }
 }
 impl StructureData {
+  /// Get the hits of this structure.
+  /// Structures that can't be damaged have 0.
+  pub fn hits(&self) -> u32 {
+    self.hits
+  }
+
+  /// Get the max hits of this structure
+  pub fn max_hits(&self) -> u32 {
+    self.max_hits
+  }
+
+  /// The hits this structure should be repaired up to.
+  /// Walls and ramparts stop at `wall_hits` and everything else is repaired
+  /// to full.
+  pub fn repair_target(&self, wall_hits: u32) -> u32 {
+    match self.structure_type {
+      StructureType::Wall | StructureType::Rampart => {
+        wall_hits.min(self.max_hits)
+      }
+      _ => self.max_hits,
+    }
+  }
+
+  /// Returns true if this structure is below the hits it should be repaired to
+  pub fn needs_repair(&self, wall_hits: u32) -> bool {
+    self.hits < self.repair_target(wall_hits)
+  }
+
+  /// Get the type of this structure
+  pub fn structure_type(&self) -> StructureType {
+    self.structure_type
+  }
+
+  /// Get the amount of a resource stored
+  pub fn store_of(&self, r: ResourceType) -> u32 {
+    self.resources.get(&r).cloned().unwrap_or(0)
+  }
+
+  /// Get everything stored
+  pub fn store(&self) -> &HashMap<ResourceType, u32> {
+    &self.resources
+  }
+
   /// Returns the Structure that this StructureData refers to
   /// PANICS if the structure isn't there
   pub fn structure(&self) -> Option<Structure> {
@@ -59,3 +138,33 @@ impl StructureData {
     game::rooms::get(self.pos.pos().room_name()).is_some()
   }
 }
+
+/// Structures for fixtures, kept up to date by the simulator
+#[cfg(any(test, feature = "native"))]
+impl StructureData {
+  /// Build structure data
+  pub fn new(pos: Position, structure_type: StructureType) -> Self {
+    StructureData {
+      pos: pos.into(),
+      structure_type,
+      resources: HashMap::new(),
+      hits: 0,
+      max_hits: 0,
+    }
+  }
+
+  /// Change the hits of this structure
+  pub fn set_hits(&mut self, hits: u32, max_hits: u32) {
+    self.hits = hits;
+    self.max_hits = max_hits;
+  }
+
+  /// Change how much of a resource is stored
+  pub fn set_store(&mut self, r: ResourceType, amount: u32) {
+    if amount == 0 {
+      self.resources.remove(&r);
+    } else {
+      self.resources.insert(r, amount);
+    }
+  }
+}
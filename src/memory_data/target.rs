@@ -1,7 +1,7 @@
 use crate::*;
 
 /// This is all the info required to identify a target given a RawObjectId
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 pub enum Target {
   /// Holds a standard structure type
   Structure(StructureData),
@@ -21,6 +21,10 @@ pub enum Target {
   Creep(CommonCreepData),
   /// Holds a basic target for simple scout tasks
   Path([Position; 2]),
+  /// Holds a Controller
+  Controller(ControllerData),
+  /// Holds a dropped Resource
+  Resource(ResourceData),
 }
 
 impl HasPosition for Target {
@@ -35,6 +39,8 @@ impl HasPosition for Target {
       Target::Mineral(s) => s.pos(),
       Target::Creep(s) => s.pos(),
       Target::Path(s) => s[0],
+      Target::Controller(s) => s.pos(),
+      Target::Resource(s) => s.pos(),
     }
   }
 }
@@ -51,6 +57,31 @@ impl Target {
     p.pos().room_name() == self.pos().room_name()
   }
 
+  /// Returns true if both point at the same thing, however much it changed
+  /// since. Creeps move around so they're matched by id, paths by both ends
+  /// and everything else by where it is.
+  pub fn same_as(&self, other: &Target) -> bool {
+    match (self, other) {
+      (Target::Creep(a), Target::Creep(b)) => a.id() == b.id(),
+      (Target::Path(a), Target::Path(b)) => a == b,
+      (a, b) => {
+        std::mem::discriminant(a) == std::mem::discriminant(b)
+          && a.pos() == b.pos()
+      }
+    }
+  }
+
+  /// Get everything stored in a target that has a store
+  pub fn store(&self) -> Option<&HashMap<ResourceType, u32>> {
+    match self {
+      Target::Structure(s) => Some(s.store()),
+      Target::Tombstone(t) => Some(t.store()),
+      Target::Ruin(r) => Some(r.store()),
+      Target::Creep(c) => Some(c.store()),
+      _ => None,
+    }
+  }
+
   /// Returns a harvestable reference
   pub fn as_harvestable(&self) -> Option<Box<dyn Harvestable>> {
     match self {
@@ -61,3 +92,17 @@ impl Target {
     }
   }
 }
+
+/// Pick a resource that there is some of, preferring energy
+pub fn first_resource(
+  store: &HashMap<ResourceType, u32>,
+) -> Option<ResourceType> {
+  if store.get(&Energy).cloned().unwrap_or(0) > 0 {
+    return Some(Energy);
+  }
+  // sort so the choice doesn't depend on the map's order
+  let mut resources: Vec<ResourceType> =
+    store.iter().filter(|(_, amount)| **amount > 0).map(|(r, _)| *r).collect();
+  resources.sort_by_key(|r| format!("{:?}", r));
+  resources.into_iter().next()
+}
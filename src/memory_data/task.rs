@@ -2,9 +2,22 @@ use crate::*;
 use std::ops::DerefMut;
 
 /// This hold all the info for a task te be given to a creep
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 pub struct Task(VecDeque<(Action, Target)>);
 
+/// What came of running a step of a task for a tick
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum StepOutcome {
+  /// The step needs more ticks
+  InProgress,
+  /// The step is finished
+  Done,
+  /// The step can't be finished, and why
+  Failed(String),
+  /// Something is in the way that may clear up, so try again later
+  Blocked,
+}
+
 impl Display for Task {
   fn fmt(&self, f: &mut Formatter<'_>) -> Result {
     for (a, t) in &self.0 {
@@ -63,13 +76,98 @@ impl Task {
     todo!("Return if this is paved from {} to all the targets", source.pos())
   }
 
-  /// Get all the required body parts for a task
-  pub fn parts_required(&self) -> HashSet<Part> {
-    let mut parts = HashSet::new();
+  /// Returns true if both tasks have the same steps, whatever the state of
+  /// their targets. Executors refresh their targets every tick so this is
+  /// what decides if a task needs saving again.
+  pub fn same_steps(&self, other: &Task) -> bool {
+    self.len() == other.len()
+      && self
+        .iter()
+        .zip(other.iter())
+        .all(|((a, t), (b, u))| a == b && t.same_as(u))
+  }
+
+  /// Pack the task for creep memory the same way the Director is packed,
+  /// with a version header so older layouts can still be read
+  pub fn to_memory(&self) -> bincode::Result<String> {
+    Ok(encode_bytes(&encode(self)?))
+  }
+
+  /// Unpack a task written by `to_memory`
+  pub fn from_memory(text: &str) -> Option<Self> {
+    let data = decode_bytes(text)?;
+    decode::<Task>(&data).ok().map(|(task, _)| task)
+  }
+
+  /// Get all the required body parts for a task.
+  /// A creep needs at least one part from each group.
+  pub fn parts_required(&self) -> Vec<HashSet<Part>> {
+    let mut parts = Vec::new();
     for (a, _) in &self.0 {
-      parts = parts.union(&a.req_parts()).cloned().collect();
+      for group in a.req_parts() {
+        if !parts.contains(&group) {
+          parts.push(group);
+        }
+      }
     }
 
     parts
   }
+
+  /// The most a creep has to carry at once for the task, going by the amounts
+  /// its withdrawals name
+  pub fn capacity_required(&self) -> u32 {
+    self
+      .0
+      .iter()
+      .filter_map(|(a, _)| match a {
+        Action::Withdraw(_, amount) => *amount,
+        _ => None,
+      })
+      .max()
+      .unwrap_or(0)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn pos(x: u32) -> Position {
+    Position::new(x, 25, RoomName::new("W1N1").unwrap())
+  }
+
+  fn task() -> Task {
+    let mut task = Task::default();
+    let storage = StructureData::new(pos(20), StructureType::Storage);
+    task.push_back((
+      Action::Withdraw(Some(Energy), Some(50)),
+      Target::Structure(storage),
+    ));
+    task.push_back((Action::Scout, Target::Path([pos(25), pos(30)])));
+    task
+  }
+
+  #[test]
+  fn tasks_round_trip_through_memory() {
+    let text = task().to_memory().unwrap();
+    assert_eq!(Task::from_memory(&text), Some(task()));
+    assert_eq!(Task::from_memory("not a task"), None);
+  }
+
+  #[test]
+  fn refreshed_targets_are_the_same_steps() {
+    let mut refreshed = task();
+    if let Some((_, Target::Structure(s))) = refreshed.front_mut() {
+      s.set_store(Energy, 1_000);
+    }
+    assert_ne!(refreshed, task());
+    assert!(refreshed.same_steps(&task()));
+
+    let mut moved = task();
+    moved[1].1 = Target::Path([pos(26), pos(30)]);
+    assert!(!moved.same_steps(&task()));
+    moved.pop_back();
+    assert!(!moved.same_steps(&task()));
+  }
 }
@@ -0,0 +1,23 @@
+use crate::*;
+
+/// How a task that keeps failing has gone so far.
+/// Kept in the Director's snapshot so a VM reset doesn't give it fresh tries.
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, Clone)]
+pub struct TaskFailure {
+  /// How many times the task failed
+  pub count: u32,
+  /// Why it failed the last time
+  pub reason: String,
+  /// The creep that failed it the last time, which isn't given it again
+  pub creep: String,
+}
+
+impl Display for TaskFailure {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    write!(
+      f,
+      "failed {} times, last by {}: {}",
+      self.count, self.creep, self.reason
+    )
+  }
+}
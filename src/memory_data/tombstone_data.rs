@@ -1,7 +1,7 @@
 use crate::*;
 
 /// This serializes all the info about a structure
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 pub struct TombstoneData {
   pos: CommonData,
   resources: HashMap<ResourceType, u32>,
@@ -38,3 +38,10 @@ impl From<Tombstone> for TombstoneData {
     TombstoneData { pos, resources }
   }
 }
+
+impl TombstoneData {
+  /// Get everything left in the tombstone
+  pub fn store(&self) -> &HashMap<ResourceType, u32> {
+    &self.resources
+  }
+}
@@ -1,27 +1,48 @@
 use crate::*;
 
 /// This cleans up any memory references that are no longer needed. creeps, etc.
-pub fn cleanup_memory() -> std::result::Result<(), Box<dyn std::error::Error>> {
+pub fn cleanup_memory(world: &mut dyn World) {
   // Get all of the creeps that are still alive
-  let alive_creeps: HashSet<String> =
-    game::creeps::keys().into_iter().collect();
+  let alive_creeps: HashSet<String> = world.creep_names().into_iter().collect();
 
   // Get all of the creeps in the memory.
-  let screeps_memory = match screeps::memory::root().dict("creeps")? {
+  let memory_names = match world.creep_memory_names() {
     Some(v) => v,
     None => {
       warn!("not cleaning game creep memory: no Memory.creeps dict");
-      return Ok(());
+      return;
     }
   };
 
   // remove the ones that are dead.
-  for mem_name in screeps_memory.keys() {
+  for mem_name in memory_names {
     if !alive_creeps.contains(&mem_name) {
       debug!("cleaning up creep memory of dead creep {}", mem_name);
-      screeps_memory.del(&mem_name);
+      world.forget_creep(&mem_name);
     }
   }
 
-  Ok(())
+  // along with anything they left on the heap
+  forget_dead_creeps(&alive_creeps);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn cleanup_forgets_dead_creeps() {
+    let mut world = MockWorld::new("Player");
+    let name = RoomName::new("W1N1").unwrap();
+    let mut data = RoomData::new(name, 1);
+    let pos = Position::new(25, 25, name);
+    data.add_creep(CommonCreepData::new("Jim", mock_id(1), pos, &[Move]));
+    world.add_room(MockRoom::new(data, true));
+    world.save_task("Jim", &Task::default());
+    world.save_task("Crystal", &Task::default());
+
+    cleanup_memory(&mut world);
+    assert!(world.creep_memory.contains_key("Jim"));
+    assert!(!world.creep_memory.contains_key("Crystal"));
+  }
 }
@@ -28,13 +28,20 @@ const NAMES: [&str; 20] = [
 
 /// This gets a random name from my NAMES constant
 pub fn get_random_name(room: Room) -> String {
-  for name in NAMES.iter() {
-    let name = format!("{}_{}", name, room.name());
-    if let Some(_) = game::creeps::get(&name) {
-      continue;
-    }
-    return name;
-  }
-  // We have run out of names:
-  String::new()
+  free_name(&game::creeps::keys(), room.name()).unwrap_or_default()
+}
+
+/// The first name for a creep from a room that isn't taken yet.
+/// None once we have run out of names.
+pub fn free_name(taken: &[String], room: RoomName) -> Option<String> {
+  NAMES
+    .iter()
+    .map(|name| format!("{}_{}", name, room))
+    .find(|name| !taken.contains(name))
+}
+
+/// The room a creep was named after, which is the room that spawned it
+pub fn home_room(creep_name: &str) -> Option<RoomName> {
+  let (_, room) = creep_name.split_at(creep_name.rfind('_')? + 1);
+  RoomName::new(room).ok()
 }
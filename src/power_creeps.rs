@@ -0,0 +1,152 @@
+//! Keeps our power creeps spawned, renewed and busy.
+//!
+//! Power creeps that aren't alive are spawned at a power spawn in a room we
+//! own. An idle power creep is renewed when its ticks run low, enables powers
+//! in its room and then uses every power that's off cooldown and that the ops
+//! it carries can pay for. Power creeps short on ops for their powers top up
+//! from storage or the terminal first.
+
+use crate::*;
+
+/// The powers we use in the order we prefer them along with their ops cost
+const POWERS: [(PowerType, u32); 4] = [
+  (PowerType::GenerateOps, 0),
+  (PowerType::OperateSpawn, 100),
+  (PowerType::OperateExtension, 2),
+  (PowerType::RegenSource, 0),
+];
+
+/// Spawn the power creeps that aren't alive and give the idle ones a task
+pub fn manage_power_creeps(
+  world: &mut dyn World,
+  director: &Director,
+  creeps: &mut [MyCreepData],
+) {
+  let mut rooms: Vec<&RoomData> = director.owned_rooms().values().collect();
+  rooms.sort_by_key(|room| room.name().to_string());
+  let power_spawns: Vec<&StructureData> = rooms
+    .iter()
+    .flat_map(|room| room.structures(StructureType::PowerSpawn))
+    .collect();
+
+  let accounts = world.power_creeps();
+  for account in accounts.iter().filter(|account| !account.spawned()) {
+    let spawn = match power_spawns.first() {
+      Some(spawn) => Target::Structure((*spawn).clone()),
+      None => break,
+    };
+    match world.intent(account.name(), Intent::SpawnPowerCreep(spawn)) {
+      ReturnCode::Ok => info!("Spawning power creep {}", account.name()),
+      // still on its spawn cooldown
+      ReturnCode::Tired => {}
+      code => warn!("Unable to spawn {}: {:?}", account.name(), code),
+    }
+  }
+
+  for creep in creeps.iter_mut() {
+    if !creep.is_power_creep() || !creep.task().is_empty() {
+      continue;
+    }
+    let account = match accounts.iter().find(|a| a.name() == creep.name()) {
+      Some(account) => account,
+      None => continue,
+    };
+    if let Some(task) =
+      power_task(world, director, &power_spawns, creep, account)
+    {
+      creep.assign(task);
+    }
+  }
+}
+
+/// Work out what an idle power creep should do next
+fn power_task(
+  world: &dyn World,
+  director: &Director,
+  power_spawns: &[&StructureData],
+  creep: &MyCreepData,
+  account: &PowerCreepData,
+) -> Option<Task> {
+  let here = creep.pos();
+  let mut task = Task::default();
+
+  if creep.ticks_to_live() < power_renew_ticks(world) {
+    let spawn = power_spawns.iter().min_by_key(|spawn| {
+      let pos = spawn.pos();
+      (pos.room_name() != here.room_name(), here.get_range_to(&pos))
+    })?;
+    task.push_back((Action::Renew, Target::Structure((*spawn).clone())));
+    return Some(task);
+  }
+
+  // powers are only worth using in our own rooms
+  let room = director.owned_rooms().get(&here.room_name())?;
+  let ctrl = room.controller()?;
+  if !ctrl.power_enabled() {
+    task.push_back((Action::EnableRoom, Target::Controller(ctrl.clone())));
+    return Some(task);
+  }
+
+  let mut ops = creep.store_of(Ops);
+  let wanted = POWERS
+    .iter()
+    .filter(|(power, _)| account.can_use(*power))
+    .map(|(_, cost)| *cost)
+    .max()
+    .unwrap_or(0);
+  if ops < wanted && creep.free_capacity() > 0 {
+    let stock = [StructureType::Storage, StructureType::Terminal]
+      .iter()
+      .flat_map(|kind| room.structures(*kind))
+      .find(|stock| stock.store_of(Ops) > 0);
+    if let Some(stock) = stock {
+      let target = Target::Structure(stock.clone());
+      task.push_back((Action::Withdraw(Some(Ops), None), target));
+      return Some(task);
+    }
+  }
+
+  for (power, cost) in POWERS.iter() {
+    if !account.can_use(*power) || ops < *cost {
+      continue;
+    }
+    if let Some(target) = power_target(*power, room, creep) {
+      ops -= cost;
+      task.push_back((Action::UsePower(*power), target));
+    }
+  }
+
+  if task.is_empty() {
+    None
+  } else {
+    Some(task)
+  }
+}
+
+/// The target to use a power on
+fn power_target(
+  power: PowerType,
+  room: &RoomData,
+  creep: &MyCreepData,
+) -> Option<Target> {
+  match power {
+    // this one doesn't need a target
+    PowerType::GenerateOps => Some(Target::Creep((**creep).clone())),
+    PowerType::OperateSpawn => room
+      .structures(StructureType::Spawn)
+      .first()
+      .map(|spawn| Target::Structure(spawn.clone())),
+    // extensions are filled from storage
+    PowerType::OperateExtension => room
+      .structures(StructureType::Storage)
+      .iter()
+      .find(|storage| storage.store_of(Energy) > 0)
+      .map(|storage| Target::Structure(storage.clone())),
+    PowerType::RegenSource => room
+      .sources()
+      .iter()
+      .min_by_key(|source| source.pos().get_range_to(&creep.pos()))
+      .map(|source| Target::Source(source.clone())),
+    _ => None,
+  }
+}
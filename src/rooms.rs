@@ -17,3 +17,195 @@ pub fn manage_room(room: Room) -> ReturnCode {
 
   ReturnCode::Ok
 }
+
+/// Queue a pickup for every resource dropped in our rooms that no creep or
+/// queued task is already after. Pickups are queued in `pickup_order` from the
+/// room's storage, or its spawn when there's no storage, so the piles decaying
+/// fastest are handed out first. What's picked up is carried to storage.
+pub fn request_pickups(director: &mut Director, creeps: &[MyCreepData]) {
+  let mut pickups = vec![];
+  for room in director.owned_rooms().values() {
+    let storage = room.structures(StructureType::Storage).first();
+    let from =
+      match storage.or_else(|| room.structures(StructureType::Spawn).first()) {
+        Some(structure) => structure.pos(),
+        None => continue,
+      };
+
+    for resource in room.pickup_order(from) {
+      let pile = Target::Resource(resource.clone());
+      // piles shrink every tick so a whole task never matches a queued one
+      let picks_up = |task: &Task| {
+        task.iter().any(|(action, target)| {
+          *action == Action::Pickup && target.same_as(&pile)
+        })
+      };
+      let underway = creeps.iter().any(|creep| picks_up(creep.task()))
+        || director.task_queue().iter().any(picks_up);
+      if underway {
+        continue;
+      }
+
+      let mut task = Task::default();
+      task.push_back((Action::Pickup, pile));
+      if let Some(storage) = storage {
+        task.push_back((
+          Action::Transfer(Some(resource.resource_type()), None),
+          Target::Structure(storage.clone()),
+        ));
+      }
+      pickups.push(task);
+    }
+  }
+
+  for task in pickups {
+    director.request(task);
+  }
+}
+
+/// The order parts are picked in when any part of a group will do
+const PART_ORDER: [Part; 8] =
+  [Work, Carry, Move, Attack, RangedAttack, Heal, Claim, Tough];
+
+/// The smallest body with a part from each group and room to carry `capacity`
+pub fn body_for(parts: &[HashSet<Part>], capacity: u32) -> Vec<Part> {
+  let mut body = vec![];
+  for group in parts {
+    if body.iter().any(|part| group.contains(part)) {
+      continue;
+    }
+    if let Some(part) = PART_ORDER.iter().find(|part| group.contains(part)) {
+      body.push(*part);
+    }
+  }
+
+  let carrying = body.iter().filter(|part| **part == Carry).count() as u32;
+  let needed = (capacity + CARRY_CAPACITY - 1) / CARRY_CAPACITY;
+  body.extend((carrying..needed).map(|_| Carry));
+  body
+}
+
+/// Spawn the creeps the Director is waiting on, one per spawn a tick.
+/// Requests for the same body are only spawned once since the new creep will
+/// count as capable from the next tick on.
+pub fn spawn_requested(world: &mut dyn World, director: &Director) {
+  let mut taken = world.creep_names();
+  let mut used = HashSet::new();
+  let mut bodies = vec![];
+  for request in director.spawn_requests() {
+    let body = body_for(&request.parts, request.capacity);
+    if bodies.contains(&body) {
+      continue;
+    }
+    let spawn = director.room(request.room).and_then(|room| {
+      room
+        .structures(StructureType::Spawn)
+        .iter()
+        .find(|spawn| !used.contains(&spawn.pos()))
+        .cloned()
+    });
+    let spawn = match spawn {
+      Some(spawn) => spawn,
+      None => continue,
+    };
+    let name = match free_name(&taken, request.room) {
+      Some(name) => name,
+      None => {
+        warn!("Ran out of names for creeps from {}", request.room);
+        continue;
+      }
+    };
+
+    used.insert(spawn.pos());
+    let target = Target::Structure(spawn);
+    match world.intent(&name, Intent::SpawnCreep(target, body.clone())) {
+      ReturnCode::Ok => {
+        info!("Spawning {} with {:?}", name, body);
+        taken.push(name);
+        bodies.push(body);
+      }
+      // already spawning or still saving up
+      ReturnCode::Busy | ReturnCode::NotEnoughEnergy => {}
+      code => warn!("Unable to spawn {}: {:?}", name, code),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn room() -> RoomName {
+    RoomName::new("W1N1").unwrap()
+  }
+
+  fn repair(x: u32) -> Task {
+    let road =
+      StructureData::new(Position::new(x, 10, room()), StructureType::Road);
+    let mut task = Task::default();
+    task.push_back((Action::Repair, Target::Structure(road)));
+    task
+  }
+
+  #[test]
+  fn dropped_resources_are_picked_up_fastest_decaying_first() {
+    let mut world = MockWorld::new("Player");
+    let mut data = RoomData::new(room(), 1);
+    let storage =
+      StructureData::new(Position::new(25, 25, room()), StructureType::Storage);
+    data.add_structure(storage.clone());
+    let near = Position::new(26, 25, room());
+    let far = Position::new(40, 25, room());
+    data.add_resource(ResourceData::new(near, mock_id(1), Energy, 50));
+    data.add_resource(ResourceData::new(far, mock_id(2), Energy, 5000));
+    world.add_room(MockRoom::new(data, true));
+    let mut director = Director::new(&world);
+
+    request_pickups(&mut director, &[]);
+    let queued: Vec<Position> =
+      director.task_queue().iter().map(|task| task[0].1.pos()).collect();
+    assert_eq!(queued, vec![far, near]);
+    let task = &director.task_queue()[0];
+    assert_eq!(
+      task[1],
+      (Action::Transfer(Some(Energy), None), Target::Structure(storage))
+    );
+
+    // the piles are already queued
+    request_pickups(&mut director, &[]);
+    assert_eq!(director.task_queue().len(), 2);
+  }
+
+  #[test]
+  fn bodies_have_a_part_from_each_group() {
+    assert_eq!(
+      body_for(&Action::Repair.req_parts(), 0),
+      vec![Work, Carry, Move]
+    );
+    assert_eq!(body_for(&Action::Attack.req_parts(), 0), vec![Attack, Move]);
+    assert_eq!(
+      body_for(&Action::Pickup.req_parts(), 120),
+      vec![Carry, Move, Carry, Carry]
+    );
+  }
+
+  #[test]
+  fn tasks_nobody_can_take_are_spawned_for() {
+    let mut world = MockWorld::new("Player");
+    let mut data = RoomData::new(room(), 1);
+    let spawn =
+      StructureData::new(Position::new(25, 25, room()), StructureType::Spawn);
+    data.add_structure(spawn.clone());
+    world.add_room(MockRoom::new(data, true));
+    let mut director = Director::new(&world);
+    director.request(repair(10));
+    director.request(repair(20));
+    director.assign_tasks(&mut []);
+    assert_eq!(director.spawn_requests().len(), 2);
+
+    spawn_requested(&mut world, &director);
+    let intent =
+      Intent::SpawnCreep(Target::Structure(spawn), vec![Work, Carry, Move]);
+    assert_eq!(world.intents, vec![("Jim_W1N1".to_string(), intent)]);
+  }
+}
@@ -0,0 +1,193 @@
+//! Decides when our rooms go into safe mode and keeps safe modes in stock.
+//!
+//! Safe mode is activated when hostiles that can do harm reach a spawn or the
+//! controller, or when a critical structure loses hits while hostiles are in
+//! the room. Only one room can be in safe mode at a time. Rooms that run low
+//! on safe modes get ghodium carried from storage to their controller.
+
+use crate::*;
+use std::cell::RefCell;
+
+/// The ghodium a controller takes to generate a safe mode
+pub const SAFE_MODE_GHODIUM: u32 = 1000;
+
+/// Structures we can't afford to lose
+const CRITICAL: [StructureType; 4] = [
+  StructureType::Spawn,
+  StructureType::Storage,
+  StructureType::Terminal,
+  StructureType::Tower,
+];
+
+thread_local! {
+  /// The hits of each critical structure the last time we looked
+  static LAST_HITS: RefCell<HashMap<(StructureType, Position), u32>> =
+    RefCell::new(HashMap::new());
+}
+
+/// Where a room we own stands with safe mode
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SafeModeState {
+  /// Safe mode is on for this many more ticks
+  Active(u32),
+  /// No safe mode can be activated for this many ticks
+  Cooldown(u32),
+  /// This many safe modes are ready to be activated
+  Available(u32),
+  /// There are no safe modes left
+  Unavailable,
+}
+
+impl From<&ControllerData> for SafeModeState {
+  fn from(ctrl: &ControllerData) -> Self {
+    if ctrl.safe_mode() > 0 {
+      SafeModeState::Active(ctrl.safe_mode())
+    } else if ctrl.safe_mode_cooldown() > 0 {
+      SafeModeState::Cooldown(ctrl.safe_mode_cooldown())
+    } else if ctrl.safe_mode_available() > 0 {
+      SafeModeState::Available(ctrl.safe_mode_available())
+    } else {
+      SafeModeState::Unavailable
+    }
+  }
+}
+
+/// The safe mode state of every room we own
+pub fn safe_mode_states(
+  director: &Director,
+) -> BTreeMap<RoomName, SafeModeState> {
+  director
+    .owned_rooms()
+    .iter()
+    .filter_map(|(name, room)| {
+      room.controller().filter(|c| c.my()).map(|c| (*name, c.into()))
+    })
+    .collect()
+}
+
+/// Activate safe mode where a room is under threat and queue ghodium for
+/// rooms running low.
+/// Runs after the Director has updated so it sees this tick's rooms.
+pub fn manage_safe_mode(
+  world: &mut dyn World,
+  director: &mut Director,
+  creeps: &[MyCreepData],
+) {
+  let states = safe_mode_states(director);
+  let mut active =
+    states.values().any(|state| matches!(state, SafeModeState::Active(_)));
+
+  let mut deliveries = vec![];
+  for (name, state) in states {
+    let room = &director.owned_rooms()[&name];
+    let reason = LAST_HITS.with(|last| threat(room, &mut last.borrow_mut()));
+
+    if let (Some(reason), SafeModeState::Available(_), false) =
+      (reason, state, active)
+    {
+      match world.activate_safe_mode(name) {
+        ReturnCode::Ok => {
+          warn!("Activated safe mode in {}: {}", name, reason);
+          active = true;
+        }
+        code => error!("Unable to activate safe mode in {}: {:?}", name, code),
+      }
+    }
+
+    if let Some(task) = delivery(world, room, creeps, director.task_queue()) {
+      deliveries.push(task);
+    }
+  }
+
+  for task in deliveries {
+    director.request(task);
+  }
+}
+
+/// Describe why a room needs safe mode, if it does.
+/// Remembers the hits of the room's critical structures for next time.
+fn threat(
+  room: &RoomData,
+  last_hits: &mut HashMap<(StructureType, Position), u32>,
+) -> Option<String> {
+  let critical: Vec<&StructureData> =
+    CRITICAL.iter().flat_map(|kind| room.structures(*kind)).collect();
+  let mut damaged = None;
+  for structure in critical {
+    let key = (structure.structure_type(), structure.pos());
+    let hits = structure.hits();
+    if last_hits.insert(key, hits).map_or(false, |last| hits < last) {
+      damaged = Some(structure);
+    }
+  }
+
+  let hostiles: Vec<&CommonCreepData> =
+    room.other_creeps().iter().filter(|c| is_dangerous(c)).collect();
+  if hostiles.is_empty() {
+    return None;
+  }
+
+  let guarded: Vec<Position> = room
+    .structures(StructureType::Spawn)
+    .iter()
+    .map(|s| s.pos())
+    .chain(room.controller().map(|c| c.pos()))
+    .collect();
+  for creep in hostiles {
+    let reach = if creep.active_parts(RangedAttack) > 0 { 3 } else { 1 };
+    if let Some(pos) =
+      guarded.iter().find(|pos| creep.pos().get_range_to(*pos) <= reach)
+    {
+      return Some(format!("{} reached {}", creep.name(), pos));
+    }
+  }
+
+  damaged.map(|s| format!("{:?} @ {} lost hits", s.structure_type(), s.pos()))
+}
+
+/// Returns true if the creep has parts that can hurt a room
+fn is_dangerous(creep: &CommonCreepData) -> bool {
+  [Attack, RangedAttack, Work, Claim]
+    .iter()
+    .any(|part| creep.active_parts(*part) > 0)
+}
+
+/// A task carrying ghodium from storage to the controller if the room is
+/// low on safe modes and no creep or queued task is already doing it.
+/// Only creeps that can carry all of the ghodium at once are given it.
+fn delivery(
+  world: &dyn World,
+  room: &RoomData,
+  creeps: &[MyCreepData],
+  queue: &VecDeque<Task>,
+) -> Option<Task> {
+  let ctrl = room.controller()?;
+  if ctrl.safe_mode_available() >= safe_modes_wanted(world) {
+    return None;
+  }
+  let storage = room.structures(StructureType::Storage).first()?;
+  if storage.store_of(Ghodium) < SAFE_MODE_GHODIUM {
+    return None;
+  }
+
+  // the controller changes every tick so a whole task never matches one that
+  // is already queued
+  let delivers = |task: &Task| {
+    task.iter().any(|(action, target)| {
+      *action == Action::GenerateSafeMode && target.pos() == ctrl.pos()
+    })
+  };
+  let underway = creeps.iter().any(|creep| delivers(creep.task()))
+    || queue.iter().any(delivers);
+  if underway {
+    return None;
+  }
+
+  let mut task = Task::default();
+  task.push_back((
+    Action::Withdraw(Some(Ghodium), Some(SAFE_MODE_GHODIUM)),
+    Target::Structure(storage.clone()),
+  ));
+  task.push_back((Action::GenerateSafeMode, Target::Controller(ctrl.clone())));
+  Some(task)
+}
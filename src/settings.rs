@@ -0,0 +1,57 @@
+//! Tunable settings for the bot.
+//! Each one can be overridden from the console by setting `Memory.settings.<name>`.
+
+use crate::*;
+
+/// Read a setting override or fall back to its default
+fn setting(world: &dyn World, name: &str, default: u32) -> u32 {
+  world.setting(name).filter(|v| *v > 0).map(|v| v as u32).unwrap_or(default)
+}
+
+/// How many ticks pass between saving the Director to RawMemory
+pub fn checkpoint_interval(world: &dyn World) -> u32 {
+  setting(world, "checkpoint_interval", 10)
+}
+
+/// Power creeps are renewed once they have fewer ticks to live than this
+pub fn power_renew_ticks(world: &dyn World) -> u32 {
+  setting(world, "power_renew_ticks", 500)
+}
+
+/// How many times in a row a step can be blocked before its task is given
+/// back to the Director
+pub fn step_attempts(world: &dyn World) -> u32 {
+  setting(world, "step_attempts", 5)
+}
+
+/// How many times a task can fail before the Director drops it
+pub fn task_attempts(world: &dyn World) -> u32 {
+  setting(world, "task_attempts", 3)
+}
+
+/// Creeps that go this many ticks without a new task after finishing one are
+/// recycled
+pub fn idle_ticks(world: &dyn World) -> u32 {
+  setting(world, "idle_ticks", 100)
+}
+
+/// Idle creeps with fewer ticks to live than this are recycled right away
+pub fn recycle_ttl(world: &dyn World) -> u32 {
+  setting(world, "recycle_ttl", 150)
+}
+
+/// How many safe modes each room we own keeps in stock
+pub fn safe_modes_wanted(world: &dyn World) -> u32 {
+  setting(world, "safe_modes_wanted", 1)
+}
+
+/// The hits walls and ramparts are repaired to at each controller level
+const WALL_HITS: [u32; 9] =
+  [1, 1, 10_000, 50_000, 100_000, 300_000, 1_000_000, 3_000_000, 10_000_000];
+
+/// The hits walls and ramparts are repaired to in a room of this level.
+/// Overridden per level with `wall_hits_<level>`, e.g. `wall_hits_4`.
+pub fn wall_hits(world: &dyn World, level: u8) -> u32 {
+  let level = (level as usize).min(8);
+  setting(world, &format!("wall_hits_{}", level), WALL_HITS[level])
+}
@@ -0,0 +1,258 @@
+//! Rooms for the simulator loaded from JSON fixture files.
+//!
+//! ```json
+//! {
+//!   "room": "W1N1",
+//!   "level": 1,
+//!   "terrain": ["##########...", "#........~..."],
+//!   "controller": [25, 10],
+//!   "sources": [[10, 10], [40, 40]],
+//!   "structures": [{ "kind": "spawn", "x": 25, "y": 25 }],
+//!   "construction": [{ "kind": "extension", "x": 27, "y": 25 }],
+//!   "creeps": [{ "name": "Jim_W1N1", "x": 24, "y": 25, "body": ["work", "carry", "move"] }]
+//! }
+//! ```
+//!
+//! Terrain rows use `#` for walls and `~` for swamps. Anything else is plain and
+//! missing rows or tiles are plain too.
+
+use crate::*;
+use std::path::Path;
+
+/// Anything that can go wrong loading a fixture
+#[derive(Debug)]
+pub enum FixtureError {
+  /// The file couldn't be read
+  Io(std::io::Error),
+  /// The file isn't valid JSON for a fixture
+  Parse(serde_json::Error),
+  /// The fixture names something that doesn't exist
+  Invalid(String),
+}
+
+impl Display for FixtureError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    match self {
+      FixtureError::Io(e) => write!(f, "unable to read the fixture: {}", e),
+      FixtureError::Parse(e) => write!(f, "unable to parse the fixture: {}", e),
+      FixtureError::Invalid(e) => write!(f, "invalid fixture: {}", e),
+    }
+  }
+}
+
+impl std::error::Error for FixtureError {}
+
+impl From<std::io::Error> for FixtureError {
+  fn from(e: std::io::Error) -> Self {
+    FixtureError::Io(e)
+  }
+}
+
+impl From<serde_json::Error> for FixtureError {
+  fn from(e: serde_json::Error) -> Self {
+    FixtureError::Parse(e)
+  }
+}
+
+/// A structure or construction site in a fixture
+#[derive(Deserialize)]
+pub struct StructureFixture {
+  /// The structure type as the game names it, e.g. `spawn` or `constructedWall`
+  pub kind: String,
+  /// The x coordinate
+  pub x: u32,
+  /// The y coordinate
+  pub y: u32,
+}
+
+/// One of our creeps in a fixture
+#[derive(Deserialize)]
+pub struct CreepFixture {
+  /// The creep's name
+  pub name: String,
+  /// The x coordinate
+  pub x: u32,
+  /// The y coordinate
+  pub y: u32,
+  /// The body parts as the game names them, e.g. `work` or `ranged_attack`
+  pub body: Vec<String>,
+  /// How much energy the creep starts with
+  #[serde(default)]
+  pub energy: u32,
+}
+
+/// A single room in a fixture file
+#[derive(Deserialize)]
+pub struct RoomFixture {
+  /// The room name
+  pub room: String,
+  /// The controller level
+  #[serde(default)]
+  pub level: u8,
+  /// Set if we own the room. Defaults to true.
+  #[serde(default = "owned_by_default")]
+  pub mine: bool,
+  /// Up to 50 rows of up to 50 tiles
+  #[serde(default)]
+  pub terrain: Vec<String>,
+  /// Where the controller is
+  #[serde(default)]
+  pub controller: Option<[u32; 2]>,
+  /// Where the sources are
+  #[serde(default)]
+  pub sources: Vec<[u32; 2]>,
+  /// Finished structures
+  #[serde(default)]
+  pub structures: Vec<StructureFixture>,
+  /// Construction sites
+  #[serde(default)]
+  pub construction: Vec<StructureFixture>,
+  /// Our creeps
+  #[serde(default)]
+  pub creeps: Vec<CreepFixture>,
+}
+
+fn owned_by_default() -> bool {
+  true
+}
+
+impl RoomFixture {
+  /// Read a fixture from a JSON file
+  pub fn load<P: AsRef<Path>>(
+    path: P,
+  ) -> std::result::Result<Self, FixtureError> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&text)?)
+  }
+
+  /// Build the room, numbering object ids from `next_id`
+  pub fn build(
+    &self,
+    next_id: &mut u32,
+  ) -> std::result::Result<MockRoom, FixtureError> {
+    let name = RoomName::new(&self.room)
+      .map_err(|_| FixtureError::Invalid(format!("room {}", self.room)))?;
+    let pos = |x: u32, y: u32| Position::new(x, y, name);
+    let mut id = || {
+      *next_id += 1;
+      *next_id
+    };
+
+    let mut data = RoomData::new(name, self.level);
+
+    if let Some([x, y]) = self.controller {
+      let mut ctrl =
+        ControllerData::new(pos(x, y), mock_id(id()), self.mine, 0);
+      let total =
+        super::simulator::CONTROLLER_PROGRESS.get(self.level as usize).cloned();
+      ctrl.set_progress(self.level, 0, total.unwrap_or(0));
+      data.set_controller(ctrl);
+      data.add_structure(StructureData::new(
+        pos(x, y),
+        StructureType::Controller,
+      ));
+    }
+
+    for [x, y] in &self.sources {
+      let capacity = SOURCE_ENERGY_CAPACITY;
+      data.add_source(SourceData::new(
+        pos(*x, *y),
+        mock_id(id()),
+        capacity,
+        capacity,
+      ));
+    }
+
+    for s in &self.structures {
+      data.add_structure(StructureData::new(
+        pos(s.x, s.y),
+        structure_type(&s.kind)?,
+      ));
+    }
+
+    for s in &self.construction {
+      let structure_type = structure_type(&s.kind)?;
+      let total = construction_cost(structure_type);
+      let site = ConstructionData::new(
+        pos(s.x, s.y),
+        mock_id(id()),
+        structure_type,
+        total,
+      );
+      data.add_construction(site);
+    }
+
+    for c in &self.creeps {
+      let body = c
+        .body
+        .iter()
+        .map(|p| part(p))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+      let mut creep =
+        CommonCreepData::new(&c.name, mock_id(id()), pos(c.x, c.y), &body);
+      creep.set_store(Energy, c.energy.min(creep.free_capacity()));
+      data.add_creep(creep);
+    }
+
+    let mut room = MockRoom::new(data, self.mine);
+    room.terrain = parse_terrain(&self.terrain);
+
+    Ok(room)
+  }
+}
+
+/// Look up a structure type by the name the game uses for it
+fn structure_type(
+  kind: &str,
+) -> std::result::Result<StructureType, FixtureError> {
+  Ok(match kind {
+    "spawn" => StructureType::Spawn,
+    "extension" => StructureType::Extension,
+    "road" => StructureType::Road,
+    "constructedWall" => StructureType::Wall,
+    "rampart" => StructureType::Rampart,
+    "link" => StructureType::Link,
+    "storage" => StructureType::Storage,
+    "tower" => StructureType::Tower,
+    "observer" => StructureType::Observer,
+    "powerSpawn" => StructureType::PowerSpawn,
+    "extractor" => StructureType::Extractor,
+    "lab" => StructureType::Lab,
+    "terminal" => StructureType::Terminal,
+    "container" => StructureType::Container,
+    "nuker" => StructureType::Nuker,
+    "factory" => StructureType::Factory,
+    "controller" => StructureType::Controller,
+    _ => return Err(FixtureError::Invalid(format!("structure {}", kind))),
+  })
+}
+
+/// Look up a body part by the name the game uses for it
+fn part(name: &str) -> std::result::Result<Part, FixtureError> {
+  Ok(match name {
+    "move" => Move,
+    "work" => Work,
+    "carry" => Carry,
+    "attack" => Attack,
+    "ranged_attack" => RangedAttack,
+    "heal" => Heal,
+    "claim" => Claim,
+    "tough" => Tough,
+    _ => return Err(FixtureError::Invalid(format!("body part {}", name))),
+  })
+}
+
+/// The energy needed to build a structure
+fn construction_cost(structure_type: StructureType) -> u32 {
+  match structure_type {
+    StructureType::Spawn => 15_000,
+    StructureType::Extension => 3_000,
+    StructureType::Road => 300,
+    StructureType::Wall | StructureType::Rampart => 1,
+    StructureType::Link => 5_000,
+    StructureType::Storage => 30_000,
+    StructureType::Tower => 5_000,
+    StructureType::Container => 5_000,
+    _ => 10_000,
+  }
+}
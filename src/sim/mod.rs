@@ -0,0 +1,27 @@
+//! A deterministic offline simulator.
+//!
+//! Runs the bot against a `MockWorld` one tick at a time and then applies a
+//! simplified version of the game rules to the intents it gave. Creeps move one
+//! tile per tick, sources regenerate every 300 ticks, dropped resources decay and
+//! only a handful of intents have any effect. It is meant for checking how
+//! creeps carry out their tasks, like "upgrading with the energy they carry
+//! takes the controller to the next level", without deploying to a server.
+//!
+//! Nothing in the bot hands out harvest or upgrade tasks on its own yet, so a
+//! fresh room left to itself won't level up. Scenarios give creeps their tasks
+//! through `MockWorld::save_task`.
+//!
+//! Only recording snapshots is part of the wasm build. Everything else needs
+//! the `native` feature.
+
+#[cfg(any(test, feature = "native"))]
+pub use fixture::*;
+#[cfg(any(test, feature = "native"))]
+pub use simulator::*;
+pub use snapshot::*;
+
+#[cfg(any(test, feature = "native"))]
+mod fixture;
+#[cfg(any(test, feature = "native"))]
+mod simulator;
+mod snapshot;
@@ -0,0 +1,478 @@
+//! Steps a `MockWorld` and applies the intents the bot gave.
+
+use crate::*;
+use screeps::Terrain;
+use std::path::Path;
+
+/// The progress needed to reach the next controller level, indexed by the current level
+pub(super) const CONTROLLER_PROGRESS: [u32; 8] =
+  [0, 200, 45_000, 135_000, 405_000, 1_215_000, 3_645_000, 10_935_000];
+
+/// How often sources refill
+const REGEN_TIME: u32 = 300;
+
+/// Runs the bot against a mock world
+pub struct Simulator {
+  /// The world being simulated
+  pub world: MockWorld,
+  /// Controller progress toward the next level in each room
+  pub progress: HashMap<RoomName, u32>,
+}
+
+impl Simulator {
+  /// Start simulating a world as if the VM had just been reset
+  pub fn new(world: MockWorld) -> Self {
+    reset_director();
+    Simulator { world, progress: HashMap::new() }
+  }
+
+  /// Start simulating the rooms in a set of fixture files
+  pub fn from_fixtures<P: AsRef<Path>>(
+    username: &str,
+    paths: &[P],
+  ) -> std::result::Result<Self, FixtureError> {
+    let mut world = MockWorld::new(username);
+    let mut next_id = 0;
+    for path in paths {
+      world.add_room(RoomFixture::load(path)?.build(&mut next_id)?);
+    }
+    Ok(Simulator::new(world))
+  }
+
+  /// Replay rooms recorded with `RoomSnapshot`, starting from the tick the
+  /// newest one was recorded on
+  pub fn from_snapshots<P: AsRef<Path>>(
+    username: &str,
+    paths: &[P],
+  ) -> std::result::Result<Self, FixtureError> {
+    let mut world = MockWorld::new(username);
+    for path in paths {
+      let snapshot = RoomSnapshot::load(path)?;
+      world.time = world.time.max(snapshot.time);
+      world.creep_memory.extend(snapshot.tasks.clone());
+      world.add_room(snapshot.build());
+    }
+    Ok(Simulator::new(world))
+  }
+
+  /// Run a single tick of the bot and apply everything it did
+  pub fn step(&mut self) {
+    tick(&mut self.world);
+
+    // apply intents one creep at a time in a stable order
+    let mut intents: Vec<(String, Intent)> =
+      self.world.intents.drain(..).collect();
+    intents.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut acted = HashSet::new();
+    for (creep, intent) in intents {
+      // each creep only gets one intent of each kind per tick
+      if acted.insert((creep.clone(), std::mem::discriminant(&intent))) {
+        self.apply(&creep, intent);
+      }
+    }
+
+    self.regenerate();
+    self.decay();
+    self.world.next_tick();
+  }
+
+  /// Step until `done` returns true, giving up after `max_ticks`.
+  /// Returns the number of ticks it took.
+  pub fn run_until<F: Fn(&Simulator) -> bool>(
+    &mut self,
+    max_ticks: u32,
+    done: F,
+  ) -> Option<u32> {
+    for ticks in 0..max_ticks {
+      if done(self) {
+        return Some(ticks);
+      }
+      self.step();
+    }
+    None
+  }
+
+  /// The controller level of a room
+  pub fn level(&self, room: RoomName) -> u8 {
+    self.world.rooms.get(&room).map(|r| r.data.level()).unwrap_or(0)
+  }
+
+  /// Apply a single intent
+  fn apply(&mut self, name: &str, intent: Intent) {
+    let creep = match self.world.creep(name) {
+      Some(creep) => creep,
+      None => return,
+    };
+    let room_name = creep.pos().room_name();
+
+    match intent {
+      Intent::MoveTo(pos) => {
+        if let Some(next) = self.next_step(creep.pos(), pos) {
+          if let Some(c) = self.world.creep_mut(name) {
+            c.set_pos(next);
+          }
+        }
+      }
+      Intent::Harvest(Target::Source(s)) => {
+        if creep.pos().get_range_to(&s.pos()) > 1 {
+          return;
+        }
+        let room = match self.world.rooms.get_mut(&room_name) {
+          Some(room) => room,
+          None => return,
+        };
+        let source = match room
+          .data
+          .sources_mut()
+          .iter_mut()
+          .find(|source| source.id() == s.id())
+        {
+          Some(source) => source,
+          None => return,
+        };
+        let harvested = creep.harvesting_power().min(source.amount());
+        let amount = harvested.min(creep.free_capacity());
+        source.set_amount(source.amount() - harvested);
+        // anything that doesn't fit drops into a container underneath
+        let pos = creep.pos();
+        if let Some(container) =
+          room.data.structure_mut(pos, StructureType::Container)
+        {
+          let overflow = harvested - amount;
+          let r = container.store_of(Energy);
+          let free = store_capacity(StructureType::Container) - r;
+          container.set_store(Energy, r + overflow.min(free));
+        }
+        if let Some(c) = self.world.creep_mut(name) {
+          c.set_store(Energy, creep.store_of(Energy) + amount);
+        }
+      }
+      Intent::Build(Target::ConstructionSite(c)) => {
+        if creep.pos().get_range_to(&c.pos()) > 3 {
+          return;
+        }
+        let room = match self.world.rooms.get_mut(&room_name) {
+          Some(room) => room,
+          None => return,
+        };
+        let site = match room.data.construction_mut(c.id()) {
+          Some(site) => site,
+          None => return,
+        };
+        let amount = creep
+          .build_power()
+          .min(creep.store_of(Energy))
+          .min(site.progress_total() - site.progress());
+        site.set_progress(site.progress() + amount);
+        if site.progress() >= site.progress_total() {
+          let structure_type = site.structure_type();
+          room.data.remove_construction(c.id());
+          room.data.add_structure(StructureData::new(c.pos(), structure_type));
+        }
+        if let Some(c) = self.world.creep_mut(name) {
+          c.set_store(Energy, creep.store_of(Energy) - amount);
+        }
+      }
+      Intent::Repair(Target::Structure(s)) => {
+        if creep.pos().get_range_to(&s.pos()) > 3 {
+          return;
+        }
+        let room = match self.world.rooms.get_mut(&room_name) {
+          Some(room) => room,
+          None => return,
+        };
+        let structure =
+          match room.data.structure_mut(s.pos(), s.structure_type()) {
+            Some(structure) => structure,
+            None => return,
+          };
+        let spent = creep.active_parts(Work).min(creep.store_of(Energy));
+        let hits =
+          (structure.hits() + spent * REPAIR_POWER).min(structure.max_hits());
+        structure.set_hits(hits, structure.max_hits());
+        if let Some(c) = self.world.creep_mut(name) {
+          c.set_store(Energy, creep.store_of(Energy) - spent);
+        }
+      }
+      Intent::Transfer(Target::Structure(s), r, amount) => {
+        if creep.pos().get_range_to(&s.pos()) > 1 {
+          return;
+        }
+        let room = match self.world.rooms.get_mut(&room_name) {
+          Some(room) => room,
+          None => return,
+        };
+        let structure =
+          match room.data.structure_mut(s.pos(), s.structure_type()) {
+            Some(structure) => structure,
+            None => return,
+          };
+        let free = store_capacity(s.structure_type())
+          .saturating_sub(structure.store_of(r));
+        let amount =
+          amount.unwrap_or(std::u32::MAX).min(creep.store_of(r)).min(free);
+        structure.set_store(r, structure.store_of(r) + amount);
+        if let Some(c) = self.world.creep_mut(name) {
+          c.set_store(r, creep.store_of(r) - amount);
+        }
+      }
+      Intent::Pickup(Target::Resource(r)) => {
+        if creep.pos().get_range_to(&r.pos()) > 1 {
+          return;
+        }
+        let room = match self.world.rooms.get_mut(&room_name) {
+          Some(room) => room,
+          None => return,
+        };
+        let resource =
+          match room.data.resources_mut().iter_mut().find(|o| o.id() == r.id())
+          {
+            Some(resource) => resource,
+            None => return,
+          };
+        let amount = resource.amount().min(creep.free_capacity());
+        let resource_type = resource.resource_type();
+        resource.set_amount(resource.amount() - amount);
+        room.data.resources_mut().retain(|r| r.amount() > 0);
+        if let Some(c) = self.world.creep_mut(name) {
+          c.set_store(resource_type, creep.store_of(resource_type) + amount);
+        }
+      }
+      Intent::Withdraw(Target::Structure(s), r, amount) => {
+        if creep.pos().get_range_to(&s.pos()) > 1 {
+          return;
+        }
+        let room = match self.world.rooms.get_mut(&room_name) {
+          Some(room) => room,
+          None => return,
+        };
+        let structure =
+          match room.data.structure_mut(s.pos(), s.structure_type()) {
+            Some(structure) => structure,
+            None => return,
+          };
+        let amount = amount
+          .unwrap_or(std::u32::MAX)
+          .min(structure.store_of(r))
+          .min(creep.free_capacity());
+        structure.set_store(r, structure.store_of(r) - amount);
+        if let Some(c) = self.world.creep_mut(name) {
+          c.set_store(r, creep.store_of(r) + amount);
+        }
+      }
+      Intent::UpgradeController(room) => {
+        if !self.world.is_mine(room) {
+          return;
+        }
+        let ctrl = self
+          .world
+          .rooms
+          .get(&room)
+          .and_then(|r| r.data.controller().map(|c| c.pos()));
+        let ctrl = match ctrl {
+          Some(ctrl) => ctrl,
+          None => return,
+        };
+        if creep.pos().get_range_to(&ctrl) > 3 {
+          return;
+        }
+        let amount = creep.upgrade_power().min(creep.store_of(Energy));
+        if let Some(c) = self.world.creep_mut(name) {
+          c.set_store(Energy, creep.store_of(Energy) - amount);
+        }
+        self.add_progress(room, amount);
+      }
+      Intent::Recycle(Target::Structure(s)) => {
+        if creep.pos().get_range_to(&s.pos()) <= 1 {
+          self.remove_creep(name);
+        }
+      }
+      Intent::Suicide => self.remove_creep(name),
+      other => trace!("The simulator ignores {:?}", other),
+    }
+  }
+
+  /// Add controller progress, leveling the room up when it's reached
+  fn add_progress(&mut self, name: RoomName, amount: u32) {
+    let room = match self.world.rooms.get_mut(&name) {
+      Some(room) => room,
+      None => return,
+    };
+    let progress = self.progress.entry(name).or_insert(0);
+    *progress += amount;
+
+    let mut level = room.data.level() as usize;
+    if level < 8 && *progress >= CONTROLLER_PROGRESS[level] {
+      *progress -= CONTROLLER_PROGRESS[level];
+      level += 1;
+      room.data.set_level(level as u8);
+      info!("{} reached level {}", name, level);
+    }
+
+    let total = CONTROLLER_PROGRESS.get(level).cloned().unwrap_or(0);
+    if let Some(ctrl) = room.data.controller_mut() {
+      ctrl.set_progress(level as u8, *progress, total);
+    }
+  }
+
+  /// Take one of our creeps out of the world
+  fn remove_creep(&mut self, name: &str) {
+    for room in self.world.rooms.values_mut() {
+      room.data.my_creeps_mut().retain(|creep| creep.name() != name);
+    }
+  }
+
+  /// Refill sources on a fixed cadence
+  fn regenerate(&mut self) {
+    if self.world.time % REGEN_TIME != 0 {
+      return;
+    }
+    for room in self.world.rooms.values_mut() {
+      for source in room.data.sources_mut() {
+        source.set_amount(source.capacity());
+      }
+    }
+  }
+
+  /// Dropped resources lose some of what's left every tick
+  fn decay(&mut self) {
+    for room in self.world.rooms.values_mut() {
+      for resource in room.data.resources_mut().iter_mut() {
+        resource.set_amount(resource.amount() - resource.decay());
+      }
+      room.data.resources_mut().retain(|r| r.amount() > 0);
+    }
+  }
+
+  /// The next tile to step onto when heading toward a position.
+  /// Stops next to anything that can't be walked on.
+  fn next_step(&self, from: Position, to: Position) -> Option<Position> {
+    if from.room_name() != to.room_name() {
+      trace!("The simulator can't move between rooms");
+      return None;
+    }
+
+    let range = from.get_range_to(&to);
+    if range == 0 || (range == 1 && !self.walkable(to)) {
+      return None;
+    }
+
+    let dx = (to.x() as i32 - from.x() as i32).signum();
+    let dy = (to.y() as i32 - from.y() as i32).signum();
+    // try a diagonal first and then each axis on its own
+    [(dx, dy), (dx, 0), (0, dy)]
+      .iter()
+      .filter(|(x, y)| *x != 0 || *y != 0)
+      .map(|(x, y)| {
+        Position::new(
+          (from.x() as i32 + x) as u32,
+          (from.y() as i32 + y) as u32,
+          from.room_name(),
+        )
+      })
+      .find(|pos| self.walkable(*pos) && !self.occupied(*pos))
+  }
+
+  /// Returns true if a creep could stand on this tile
+  fn walkable(&self, pos: Position) -> bool {
+    self.world.terrain(pos) != Terrain::Wall
+      && self
+        .world
+        .rooms
+        .get(&pos.room_name())
+        .map(|room| !room.data.is_blocked(pos))
+        .unwrap_or(false)
+  }
+
+  /// Returns true if a creep is already standing here
+  fn occupied(&self, pos: Position) -> bool {
+    self
+      .world
+      .rooms
+      .get(&pos.room_name())
+      .map(|r| r.data.my_creeps().iter().any(|c| c.pos() == pos))
+      .unwrap_or(false)
+  }
+}
+
+/// How much of a single resource a structure can hold
+fn store_capacity(structure_type: StructureType) -> u32 {
+  match structure_type {
+    StructureType::Spawn => 300,
+    StructureType::Extension => 50,
+    StructureType::Container => 2_000,
+    StructureType::Tower => 1_000,
+    StructureType::Link => 800,
+    StructureType::Storage => 1_000_000,
+    StructureType::Terminal => 300_000,
+    _ => 0,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn rcl1() -> Simulator {
+    let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/rcl1.json");
+    Simulator::from_fixtures("Player", &[fixture]).unwrap()
+  }
+
+  fn room() -> RoomName {
+    RoomName::new("W1N1").unwrap()
+  }
+
+  #[test]
+  fn fixtures_build_the_room() {
+    let sim = rcl1();
+    let data = &sim.world.rooms[&room()].data;
+    assert_eq!(sim.level(room()), 1);
+    assert_eq!(data.sources().len(), 2);
+    assert_eq!(data.structures(StructureType::Spawn).len(), 1);
+    assert_eq!(data.my_creeps().len(), 2);
+    assert!(data.controller().unwrap().my());
+  }
+
+  /// Have every creep upgrade the controller with the energy it carries
+  fn upgrade_with_energy(sim: &mut Simulator) {
+    let ctrl = sim.world.rooms[&room()].data.controller().cloned().unwrap();
+    let names: Vec<String> =
+      sim.world.my_creeps().iter().map(|c| c.name().to_string()).collect();
+    for name in names {
+      sim.world.creep_mut(&name).unwrap().set_store(Energy, 50);
+      let mut task = Task::default();
+      task.push_back((Action::Upgrade, Target::Controller(ctrl.clone())));
+      sim.world.save_task(&name, &task);
+    }
+  }
+
+  #[test]
+  fn creeps_spend_their_energy_upgrading() {
+    let mut sim = rcl1();
+    upgrade_with_energy(&mut sim);
+
+    let upgraded = |sim: &Simulator| {
+      sim.progress.get(&room()).map_or(false, |progress| *progress >= 100)
+    };
+    assert!(sim.run_until(200, upgraded).is_some());
+    assert_eq!(sim.level(room()), 1);
+    for creep in sim.world.my_creeps() {
+      assert_eq!(creep.store_of(Energy), 0);
+    }
+  }
+
+  #[test]
+  fn enough_progress_levels_the_controller_up() {
+    let mut sim = rcl1();
+    sim.progress.insert(room(), CONTROLLER_PROGRESS[1] - 50);
+    upgrade_with_energy(&mut sim);
+
+    let spent = |sim: &Simulator| {
+      sim.world.my_creeps().iter().all(|c| c.store_of(Energy) == 0)
+    };
+    assert!(sim.run_until(200, spent).is_some());
+    assert_eq!(sim.level(room()), 2);
+    // what went past the threshold counts toward the next level
+    assert_eq!(sim.progress[&room()], 50);
+  }
+}
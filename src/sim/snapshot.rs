@@ -0,0 +1,166 @@
+//! Recordings of a single room at a single tick.
+//!
+//! A snapshot holds everything the bot saw in a room along with the terrain
+//! and the tasks our creeps there were carrying. They're recorded from the
+//! console with `snapshot_room("W1N1")` and replayed with
+//! `Simulator::from_snapshots` to reproduce what the creeps did.
+
+use crate::*;
+use screeps::Terrain;
+#[cfg(any(test, feature = "native"))]
+use std::path::Path;
+
+/// A room as it was at a single tick
+#[derive(Serialize, Deserialize)]
+pub struct RoomSnapshot {
+  /// The tick it was recorded on
+  pub time: u32,
+  /// Set if we owned the room
+  pub mine: bool,
+  /// Everything in the room
+  pub data: RoomData,
+  /// 50 rows of terrain in the same format as fixtures
+  pub terrain: Vec<String>,
+  /// The task each of our creeps in the room was carrying
+  pub tasks: BTreeMap<String, Task>,
+}
+
+impl RoomSnapshot {
+  /// Record a visible room
+  pub fn record(world: &dyn World, name: RoomName) -> Option<Self> {
+    let data = world.room(name)?;
+
+    let terrain = (0..50)
+      .map(|y| {
+        (0..50).map(|x| world.terrain(Position::new(x, y, name))).collect()
+      })
+      .map(|row: Vec<Terrain>| terrain_row(&row))
+      .collect();
+
+    let tasks = world
+      .my_creeps()
+      .into_iter()
+      .filter(|creep| creep.pos().room_name() == name)
+      .map(|creep| (creep.name().to_string(), creep.task().clone()))
+      .collect();
+
+    Some(RoomSnapshot {
+      time: world.time(),
+      mine: world.is_mine(name),
+      data,
+      terrain,
+      tasks,
+    })
+  }
+
+  /// Read a snapshot from a JSON file
+  #[cfg(any(test, feature = "native"))]
+  pub fn load<P: AsRef<Path>>(
+    path: P,
+  ) -> std::result::Result<Self, FixtureError> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&text)?)
+  }
+
+  /// Write the snapshot as JSON
+  pub fn to_json(&self) -> serde_json::Result<String> {
+    serde_json::to_string(self)
+  }
+
+  /// Build the room for a `MockWorld`
+  #[cfg(any(test, feature = "native"))]
+  pub fn build(&self) -> MockRoom {
+    let mut room = MockRoom::new(self.data.clone(), self.mine);
+    room.terrain = parse_terrain(&self.terrain);
+    room
+  }
+}
+
+/// Record a room in the live game as JSON.
+/// Registered as the `snapshot_room` console command.
+pub fn snapshot_room(name: String) -> String {
+  let name = match RoomName::new(&name) {
+    Ok(name) => name,
+    Err(_) => return format!("{} isn't a room name", name),
+  };
+  match RoomSnapshot::record(&ScreepsWorld, name) {
+    Some(snapshot) => snapshot
+      .to_json()
+      .unwrap_or_else(|e| format!("unable to write the snapshot: {}", e)),
+    None => format!("{} isn't visible", name),
+  }
+}
+
+/// Write a row of terrain using `#` for walls, `~` for swamps and `.` for
+/// plains
+pub fn terrain_row(row: &[Terrain]) -> String {
+  row
+    .iter()
+    .map(|tile| match tile {
+      Terrain::Wall => '#',
+      Terrain::Swamp => '~',
+      Terrain::Plain => '.',
+    })
+    .collect()
+}
+
+/// Read up to 50 rows of terrain written by `terrain_row`.
+/// Missing rows or tiles are plain.
+#[cfg(any(test, feature = "native"))]
+pub fn parse_terrain(rows: &[String]) -> Vec<Terrain> {
+  let mut terrain = vec![Terrain::Plain; 2500];
+  for (y, row) in rows.iter().take(50).enumerate() {
+    for (x, tile) in row.chars().take(50).enumerate() {
+      terrain[y * 50 + x] = match tile {
+        '#' => Terrain::Wall,
+        '~' => Terrain::Swamp,
+        _ => Terrain::Plain,
+      };
+    }
+  }
+  terrain
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn recorded_rooms_replay() {
+    let name = RoomName::new("W1N1").unwrap();
+    let pos = |x, y| Position::new(x, y, name);
+    let mut world = MockWorld::new("Player");
+    world.time = 1_234;
+    let mut data = RoomData::new(name, 2);
+    data.add_creep(CommonCreepData::new(
+      "Jim",
+      mock_id(1),
+      pos(25, 25),
+      &[Move],
+    ));
+    let mut room = MockRoom::new(data, true);
+    room.terrain[0] = Terrain::Wall;
+    room.terrain[1] = Terrain::Swamp;
+    world.add_room(room);
+    let mut task = Task::default();
+    task.push_back((Action::Scout, Target::Path([pos(30, 25), pos(40, 25)])));
+    world.save_task("Jim", &task);
+
+    let snapshot = RoomSnapshot::record(&world, name).unwrap();
+    let path = std::env::temp_dir().join("recorded_rooms_replay.json");
+    std::fs::write(&path, snapshot.to_json().unwrap()).unwrap();
+    let mut sim = Simulator::from_snapshots("Player", &[&path]).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(sim.world.time, 1_234);
+    assert_eq!(sim.world.creep_memory["Jim"], task);
+    let replayed = &sim.world.rooms[&name];
+    assert_eq!(replayed.data, world.rooms[&name].data);
+    assert_eq!(replayed.terrain, world.rooms[&name].terrain);
+
+    // the creep picks up its task where it left off
+    let moved =
+      |sim: &Simulator| sim.world.creep("Jim").unwrap().pos().x() > 25;
+    assert!(sim.run_until(10, moved).is_some());
+  }
+}
@@ -0,0 +1,47 @@
+//! Reads a Director out of text copied from the game.
+//!
+//! A dump is either the Memory JSON, which holds the Director under
+//! `Memory.Director` for saves made before segments were used, or the output
+//! of the `dump_director()` console command: the Director's snapshot followed
+//! by each bucket of rooms, one per line, packed the way they're saved.
+
+use crate::*;
+
+/// Anything that can go wrong reading a dump
+#[derive(Debug)]
+pub enum DumpError {
+  /// The Memory JSON doesn't hold a Director
+  Missing,
+  /// The saved Director couldn't be decoded
+  Schema(SchemaError),
+}
+
+impl Display for DumpError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    match self {
+      DumpError::Missing => write!(f, "Memory.{} is missing", DIRECTOR_KEY),
+      DumpError::Schema(e) => write!(f, "unable to decode the Director: {}", e),
+    }
+  }
+}
+
+impl std::error::Error for DumpError {}
+
+impl From<SchemaError> for DumpError {
+  fn from(e: SchemaError) -> Self {
+    DumpError::Schema(e)
+  }
+}
+
+/// Decode the Director held in a dump
+pub fn read_dump(text: &str) -> std::result::Result<Director, DumpError> {
+  if let Ok(memory) = serde_json::from_str::<serde_json::Value>(text) {
+    let data: Vec<u8> = memory
+      .get(DIRECTOR_KEY)
+      .and_then(|data| serde_json::from_value(data.clone()).ok())
+      .ok_or(DumpError::Missing)?;
+    return Ok(Director::from_saved(&data)?);
+  }
+
+  Ok(Director::from_dump(text)?)
+}
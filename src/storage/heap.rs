@@ -0,0 +1,82 @@
+//! Keeps the Director alive on the heap between ticks.
+//!
+//! The wasm module is only instantiated again after a VM reset so anything in
+//! here survives until then. RawMemory is only used as a checkpoint to recover
+//! from those resets.
+
+use crate::*;
+use std::cell::{Cell, RefCell};
+
+thread_local! {
+  static DIRECTOR: RefCell<Option<Director>> = RefCell::new(None);
+  static LAST_CHECKPOINT: Cell<u32> = Cell::new(0);
+}
+
+/// Run something with the heap Director, loading it first after a VM reset.
+/// Fails only while waiting for the RawMemory segments to become active.
+pub fn with_director<R, F>(
+  world: &mut dyn World,
+  f: F,
+) -> std::result::Result<R, SegmentError>
+where
+  F: FnOnce(&mut Director, &mut dyn World) -> R,
+{
+  DIRECTOR.with(|cell| {
+    let mut cell = cell.borrow_mut();
+    if cell.is_none() {
+      info!("Loading the Director after a VM reset");
+      *cell = Some(Director::load(world)?);
+      LAST_CHECKPOINT.with(|t| t.set(world.time()));
+    }
+
+    Ok(f(cell.as_mut().expect("the Director was just loaded"), world))
+  })
+}
+
+/// Pack the heap Director for the inspect tool.
+/// Registered as the `dump_director` console command.
+pub fn dump_director() -> String {
+  DIRECTOR.with(|cell| match &*cell.borrow() {
+    Some(director) => director
+      .dump()
+      .unwrap_or_else(|e| format!("unable to pack the Director: {}", e)),
+    None => "the Director isn't loaded yet".to_string(),
+  })
+}
+
+/// List the tasks that keep failing along with why they failed last.
+/// Registered as the `task_failures` console command.
+pub fn task_failures() -> String {
+  DIRECTOR.with(|cell| match &*cell.borrow() {
+    Some(director) => director
+      .failures()
+      .iter()
+      .map(|((action, pos), failure)| {
+        format!("{:?} @ {}: {}", action, pos, failure)
+      })
+      .collect::<Vec<String>>()
+      .join("\n"),
+    None => "the Director isn't loaded yet".to_string(),
+  })
+}
+
+/// Throw away the heap Director as if the VM was reset
+pub fn reset_director() {
+  DIRECTOR.with(|cell| cell.replace(None));
+}
+
+/// Save the Director to RawMemory if enough ticks have passed since the last checkpoint
+pub fn checkpoint(world: &mut dyn World, director: &mut Director) -> bool {
+  let time = world.time();
+  let last = LAST_CHECKPOINT.with(|t| t.get());
+  if time.saturating_sub(last) < checkpoint_interval(world) {
+    return false;
+  }
+
+  // Only move the checkpoint forward if it worked so we try again next tick.
+  let saved = director.save(world);
+  if saved {
+    LAST_CHECKPOINT.with(|t| t.set(time));
+  }
+  saved
+}
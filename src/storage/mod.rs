@@ -0,0 +1,17 @@
+#[cfg(any(test, feature = "native"))]
+pub use dump::*;
+pub use encoding::*;
+pub use heap::*;
+pub use room_cache::*;
+pub use schema::*;
+pub use segments::*;
+pub use snapshots::*;
+
+#[cfg(any(test, feature = "native"))]
+mod dump;
+mod encoding;
+mod heap;
+mod room_cache;
+mod schema;
+mod segments;
+mod snapshots;
@@ -0,0 +1,190 @@
+//! Saves the rooms in RawMemory segments of their own.
+//!
+//! Rooms make up most of the Director so they're kept out of its snapshot.
+//! Every room belongs to one of `ROOM_BUCKETS` buckets picked by its name and
+//! each bucket is saved in its own segment. Only buckets holding a room whose
+//! lasting data changed since it was last saved get encoded and written again,
+//! so unchanged rooms cost nothing.
+//!
+//! Like the Director's snapshots every bucket carries a checksum and has two
+//! slots. Saves alternate between them so the last good copy of a bucket is
+//! kept as a backup when the newest one turns out to be bad.
+//!
+//! Only 10 segments can be active at once so after a VM reset the buckets are
+//! read back a few at a time over the next ticks. A bucket is never written
+//! before it's been read so rooms that haven't been read yet aren't lost.
+
+use crate::*;
+use std::collections::BTreeSet;
+
+/// The first segment holding rooms, right after the Director's snapshots
+pub const ROOM_FIRST_SEGMENT: u32 = FIRST_SEGMENT + MAX_SEGMENTS;
+/// How many buckets the rooms are spread across
+pub const ROOM_BUCKETS: u32 = 30;
+/// How many buckets are read back in a single tick
+const BUCKET_READS: usize = (MAX_SEGMENTS / SLOTS) as usize;
+/// The most buckets written in a single save. The game only saves 10 segments
+/// a tick and the Director's snapshot may need a slot's worth of them.
+const BUCKET_WRITES: usize = (MAX_SEGMENTS - SLOT_SEGMENTS) as usize;
+
+/// The bucket a room is saved in
+pub fn bucket_of(name: RoomName) -> u32 {
+  checksum(name.to_string().as_bytes()) % ROOM_BUCKETS
+}
+
+/// The segment a slot of a bucket is saved in
+pub fn bucket_segment(bucket: u32, slot: u32) -> u32 {
+  ROOM_FIRST_SEGMENT + slot * ROOM_BUCKETS + bucket
+}
+
+/// Every segment of a bucket
+fn bucket_segments(bucket: u32) -> impl Iterator<Item = u32> {
+  (0..SLOTS).map(move |slot| bucket_segment(bucket, slot))
+}
+
+/// The rooms that changed since they were last saved
+#[derive(Default)]
+pub struct RoomCache {
+  dirty: HashSet<RoomName>,
+  /// The buckets that haven't been read back since the VM reset
+  unread: BTreeSet<u32>,
+  /// The slot holding the copy of each bucket we're running from.
+  /// New copies are written to the other slot.
+  slots: HashMap<u32, u32>,
+  /// Everything saved outside of the rooms, as of the last save
+  rest: Vec<u8>,
+}
+
+impl RoomCache {
+  /// Store a freshly built room in `rooms`, marking it dirty if it changed.
+  /// The room is removed from `others` if it was there.
+  pub fn track(
+    &mut self,
+    rooms: &mut HashMap<RoomName, RoomData>,
+    others: &mut HashMap<RoomName, RoomData>,
+    name: RoomName,
+    data: RoomData,
+  ) {
+    let moved = others.remove(&name).is_some();
+    let changed = rooms.get(&name).map_or(true, |old| data.has_changed(old));
+    if moved || changed {
+      self.dirty.insert(name);
+    }
+    rooms.insert(name, data);
+  }
+
+  /// Mark a room as changed
+  pub fn mark(&mut self, name: RoomName) {
+    self.dirty.insert(name);
+  }
+
+  /// Get the buckets to write this save, which hold a changed room and have
+  /// already been read back
+  pub fn due(&self) -> Vec<u32> {
+    let buckets: BTreeSet<u32> = self
+      .dirty
+      .iter()
+      .map(|name| bucket_of(*name))
+      .filter(|bucket| !self.unread.contains(bucket))
+      .collect();
+    buckets.into_iter().take(BUCKET_WRITES).collect()
+  }
+
+  /// Forget the rooms in a bucket changed once it's been written
+  fn bucket_saved(&mut self, bucket: u32, slot: u32) {
+    self.dirty.retain(|name| bucket_of(*name) != bucket);
+    self.slots.insert(bucket, slot);
+  }
+
+  /// Remember which copy of a bucket we're running from
+  /// so the next save doesn't overwrite it.
+  pub fn used(&mut self, bucket: u32, slot: u32) {
+    self.slots.insert(bucket, slot);
+  }
+
+  /// Write the packed rooms of a bucket to the slot we're not running from
+  pub fn save_bucket(
+    &mut self,
+    world: &mut dyn World,
+    bucket: u32,
+    data: &[u8],
+  ) -> std::result::Result<(), SegmentError> {
+    let slot = (self.slots.get(&bucket).cloned().unwrap_or(0) + 1) % SLOTS;
+    let time = world.time();
+    save_range(world, bucket_segment(bucket, slot), 1, &seal(data), time)?;
+    self.bucket_saved(bucket, slot);
+    Ok(())
+  }
+
+  /// Start reading every bucket back
+  pub fn read_all(&mut self) {
+    self.unread = (0..ROOM_BUCKETS).collect();
+  }
+
+  /// Read the buckets whose segments are active and ask for the next few.
+  /// Returns every copy of each bucket that passed its checksum, newest first.
+  pub fn read(&mut self, world: &mut dyn World) -> Vec<(u32, Vec<Snapshot>)> {
+    if self.unread.is_empty() {
+      return vec![];
+    }
+
+    let active = world.active_segments();
+    let ready: Vec<u32> = self
+      .unread
+      .iter()
+      .cloned()
+      .filter(|bucket| bucket_segments(*bucket).all(|id| active.contains(&id)))
+      .collect();
+    let mut read = vec![];
+    for bucket in ready {
+      self.unread.remove(&bucket);
+      read.push((bucket, load_bucket(world, bucket)));
+    }
+
+    if self.unread.is_empty() {
+      info!("Read back every saved room");
+      // nothing has to stay active so saves get the whole write budget
+      world.set_active_segments(&[]);
+    } else {
+      let next: Vec<u32> = self
+        .unread
+        .iter()
+        .take(BUCKET_READS)
+        .flat_map(|bucket| bucket_segments(*bucket))
+        .collect();
+      world.set_active_segments(&next);
+    }
+    read
+  }
+
+  /// Returns true if everything saved outside of the rooms changed
+  pub fn rest_changed(&self, rest: &[u8]) -> bool {
+    self.rest != rest
+  }
+
+  /// Remember what was saved outside of the rooms
+  pub fn saved(&mut self, rest: Vec<u8>) {
+    self.rest = rest;
+  }
+}
+
+/// Read every copy of a bucket with a valid checksum, newest first.
+/// Copies that can't be read are logged along with the reason.
+fn load_bucket(world: &dyn World, bucket: u32) -> Vec<Snapshot> {
+  let mut copies = vec![];
+  for slot in 0..SLOTS {
+    let saved = load_range(world, bucket_segment(bucket, slot), 1);
+    match check_snapshot(slot, saved) {
+      Ok(copy) => copies.push(copy),
+      Err(SegmentError::Empty) => {
+        trace!("Slot {} of bucket {} is empty", slot, bucket)
+      }
+      Err(e) => {
+        warn!("Rejected slot {} of the rooms in bucket {}: {}", slot, bucket, e)
+      }
+    }
+  }
+
+  copies.sort_by(|a, b| b.generation.cmp(&a.generation));
+  copies
+}
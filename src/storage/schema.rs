@@ -0,0 +1,427 @@
+//! Versioning for the saved Director.
+//!
+//! Every saved Director starts with a small header holding the layout version
+//! it was written with. Creep tasks and rooms are saved with the same header
+//! so they follow the same layouts.
+//!
+//! Migrations happen while decoding. bincode payloads don't describe their
+//! fields so an old payload can only be read with the layout it was written
+//! with. Every type whose layout changed lists its older layouts with
+//! `layouts!`, each one a legacy struct or enum that reads the old layout and
+//! converts `Into` the current type, filling in what the old layout didn't
+//! carry. Decoding picks the legacy layout by `decoding_version()`.
+
+use crate::*;
+use serde::de::DeserializeOwned;
+use std::cell::Cell;
+
+/// The current layout version of the saved Director.
+/// Bump it whenever a saved type changes and add a `layouts!` entry to that
+/// type for the layout it had before.
+///
+/// 1. add a version header to the saved Director
+/// 2. track capacity, ticks to live and active parts of creeps
+/// 3. track the hits of structures
+/// 4. track the controller of each room
+/// 5. name the resource and amount of transfers and withdrawals
+/// 6. track dropped resources in each room
+/// 7. track the owner and reservation of controllers
+/// 8. let dismantling our own key structures be forced
+/// 9. keep the tiles scouts walked
+/// 10. track safe mode on controllers
+/// 11. track which rooms have powers enabled
+/// 12. save the rooms in segments of their own
+/// 13. keep how failing tasks have gone
+pub const SCHEMA_VERSION: u32 = 13;
+
+/// Marks a payload as having a version header.
+/// Payloads saved before versioning was added start with the username length
+/// instead, which can never collide with this.
+const MAGIC: [u8; 4] = *b"SDIR";
+
+/// The size of the version header in bytes.
+const HEADER_LEN: usize = 8;
+
+thread_local! {
+  static DECODING: Cell<u32> = Cell::new(SCHEMA_VERSION);
+}
+
+/// The layout version of the payload currently being deserialized.
+/// Outside of `decode` this is always `SCHEMA_VERSION`.
+pub fn decoding_version() -> u32 {
+  DECODING.with(|v| v.get())
+}
+
+/// Implements `Serialize` and `Deserialize` for a type whose layout changed.
+///
+/// The type derives both with `#[serde(remote = "Self")]` for its current
+/// layout. Each entry names the first version that stopped using an older
+/// layout along with a type that can read it and converts `Into` the current
+/// one. Entries must be listed oldest first.
+#[macro_export]
+macro_rules! layouts {
+  ($ty:ident { $($before:expr => $legacy:ty),* $(,)? }) => {
+    impl serde::Serialize for $ty {
+      fn serialize<S: serde::Serializer>(
+        &self,
+        s: S,
+      ) -> std::result::Result<S::Ok, S::Error> {
+        $ty::serialize(self, s)
+      }
+    }
+
+    impl<'de> serde::Deserialize<'de> for $ty {
+      fn deserialize<D: serde::Deserializer<'de>>(
+        d: D,
+      ) -> std::result::Result<Self, D::Error> {
+        let version = $crate::decoding_version();
+        $(
+          if version < $before {
+            return <$legacy as serde::Deserialize>::deserialize(d)
+              .map(Into::into);
+          }
+        )*
+        $ty::deserialize(d)
+      }
+    }
+  };
+}
+
+/// Anything that can go wrong while loading a saved payload
+#[derive(Debug)]
+pub enum SchemaError {
+  /// The payload was saved by a newer build than this one
+  Unsupported(u32),
+  /// The payload couldn't be deserialized
+  Corrupt(bincode::Error),
+  /// The payload is truncated or failed its checksum
+  Damaged,
+}
+
+impl Display for SchemaError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    match self {
+      SchemaError::Unsupported(v) => write!(
+        f,
+        "saved with layout version {} but this build only knows up to {}",
+        v, SCHEMA_VERSION
+      ),
+      SchemaError::Corrupt(e) => write!(f, "unable to deserialize: {}", e),
+      SchemaError::Damaged => write!(f, "truncated or failed its checksum"),
+    }
+  }
+}
+
+impl std::error::Error for SchemaError {}
+
+impl From<bincode::Error> for SchemaError {
+  fn from(e: bincode::Error) -> Self {
+    SchemaError::Corrupt(e)
+  }
+}
+
+/// The version header for a payload saved with the current layout
+pub fn header() -> Vec<u8> {
+  let mut data = Vec::with_capacity(HEADER_LEN);
+  data.extend_from_slice(&MAGIC);
+  data.extend_from_slice(&SCHEMA_VERSION.to_le_bytes());
+  data
+}
+
+/// Serialize a value with a version header
+pub fn encode<T: Serialize>(value: &T) -> bincode::Result<Vec<u8>> {
+  let mut data = header();
+  data.extend(serialize(value)?);
+  Ok(data)
+}
+
+/// Split a saved payload into its layout version and body.
+/// Payloads without a header are version 0.
+pub fn split_header(data: &[u8]) -> (u32, &[u8]) {
+  if data.len() >= HEADER_LEN && data[..4] == MAGIC {
+    let mut version = [0; 4];
+    version.copy_from_slice(&data[4..HEADER_LEN]);
+    (u32::from_le_bytes(version), &data[HEADER_LEN..])
+  } else {
+    (0, data)
+  }
+}
+
+/// Deserialize a value saved with any known layout version.
+/// Returns the value along with the version it was saved with.
+pub fn decode<T: DeserializeOwned>(
+  data: &[u8],
+) -> std::result::Result<(T, u32), SchemaError> {
+  let (version, body) = split_header(data);
+  if version > SCHEMA_VERSION {
+    return Err(SchemaError::Unsupported(version));
+  }
+
+  let previous = DECODING.with(|v| v.replace(version));
+  let value = deserialize(body);
+  DECODING.with(|v| v.set(previous));
+
+  Ok((value?, version))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A payload saved with the layout `version`
+  fn saved<T: Serialize>(version: u32, value: &T) -> Vec<u8> {
+    let mut data = MAGIC.to_vec();
+    data.extend_from_slice(&version.to_le_bytes());
+    data.extend(serialize(value).unwrap());
+    data
+  }
+
+  fn room() -> RoomName {
+    RoomName::new("W1N1").unwrap()
+  }
+
+  fn pos(x: u32, y: u32) -> Position {
+    Position::new(x, y, room())
+  }
+
+  /// A creep as it was saved before v2
+  fn creep_v1() -> impl Serialize {
+    let parts: HashSet<Part> = [Work, Carry, Move].iter().cloned().collect();
+    let store = HashMap::<ResourceType, u32>::new();
+    let (pos, id) = (CommonData::from(pos(10, 10)), mock_id::<Creep>(1));
+    (pos, "Harvester1".to_string(), id, 300u32, 300u32, parts, store)
+  }
+
+  /// A controller as it was saved before v7
+  fn controller_v6() -> impl Serialize {
+    let (pos, id) =
+      (CommonData::from(pos(20, 20)), mock_id::<StructureController>(2));
+    (pos, id, true, 2u8, 100u32, 45_000u32, 5_000u32, 0u32)
+  }
+
+  /// A controller as it was saved before v10
+  fn controller_v9() -> impl Serialize {
+    let owner = Some("Player".to_string());
+    (controller_v6(), (owner, None::<String>, 0u32))
+  }
+
+  /// A controller as it was saved before v11
+  fn controller_v10() -> impl Serialize {
+    (controller_v9(), (0u32, 3u32, 0u32))
+  }
+
+  /// A room as it was saved before v4
+  fn room_v3() -> impl Serialize {
+    let construction: HashMap<StructureType, Vec<ConstructionData>> =
+      HashMap::new();
+    let mut structures = HashMap::new();
+    // structures before v3 didn't have hits
+    let spawn = (
+      CommonData::from(pos(25, 25)),
+      StructureType::Spawn,
+      HashMap::<ResourceType, u32>::new(),
+    );
+    structures.insert(StructureType::Spawn, vec![spawn]);
+    (
+      (room(), 2u8, construction, structures),
+      (Vec::<SourceData>::new(), None::<MineralData>, None::<DepositData>),
+      (vec![creep_v1()], Vec::<()>::new(), Vec::<()>::new()),
+    )
+  }
+
+  #[test]
+  fn tuples_are_saved_field_by_field() {
+    // the fixtures nest tuples to add the fields each version brought
+    let nested = serialize(&((1u32, 2u8), 3u32)).unwrap();
+    assert_eq!(nested, serialize(&(1u32, 2u8, 3u32)).unwrap());
+  }
+
+  #[test]
+  fn headerless_director_is_version_0() {
+    let transfer = (12u32, (8u32, [pos(1, 1), pos(2, 2)]));
+    let mut rooms = HashMap::new();
+    rooms.insert(room(), room_v3());
+    let director = (
+      "Player".to_string(),
+      rooms,
+      HashMap::<RoomName, ()>::new(),
+      vec![vec![transfer]],
+    );
+    let data = serialize(&director).unwrap();
+    assert_eq!(split_header(&data).0, 0);
+
+    let director = Director::from_saved(&data).unwrap();
+    assert_eq!(director.username(), "Player");
+    let room = &director.owned_rooms()[&room()];
+    assert_eq!(room.level(), 2);
+    assert!(room.controller().is_none());
+    assert_eq!(room.structures(StructureType::Spawn)[0].max_hits(), 0);
+    let creep = &room.my_creeps()[0];
+    assert_eq!(creep.name(), "Harvester1");
+    assert_eq!(creep.capacity(), 0);
+    assert_eq!(creep.active_parts(Work), 1);
+    let (action, target) = director.task_queue()[0].front().unwrap();
+    assert_eq!(*action, Action::Transfer(Some(Energy), None));
+    assert_eq!(*target, Target::Path([pos(1, 1), pos(2, 2)]));
+  }
+
+  #[test]
+  fn creeps_before_v2() {
+    let (creep, _) = decode::<CommonCreepData>(&saved(1, &creep_v1())).unwrap();
+    assert_eq!(creep.hits(), 300);
+    assert_eq!(creep.capacity(), 0);
+    assert_eq!(creep.ticks_to_live(), 0);
+    assert_eq!(creep.active_parts(Carry), 1);
+
+    let current =
+      CommonCreepData::new("Hauler1", mock_id(3), pos(5, 5), &[Carry]);
+    let (creep, _) = decode::<CommonCreepData>(&saved(2, &current)).unwrap();
+    assert_eq!(creep, current);
+  }
+
+  #[test]
+  fn structures_before_v3() {
+    let resources = HashMap::<ResourceType, u32>::new();
+    let old = (CommonData::from(pos(3, 3)), StructureType::Road, resources);
+    let (road, _) = decode::<StructureData>(&saved(2, &old)).unwrap();
+    assert_eq!(road.structure_type(), StructureType::Road);
+    assert_eq!(road.max_hits(), 0);
+
+    let current = StructureData::new(pos(3, 3), StructureType::Road);
+    let (road, _) = decode::<StructureData>(&saved(3, &current)).unwrap();
+    assert_eq!(road, current);
+  }
+
+  #[test]
+  fn rooms_before_v4() {
+    let (data, _) = decode::<RoomData>(&saved(3, &room_v3())).unwrap();
+    assert_eq!(data.name(), room());
+    assert!(data.controller().is_none());
+    assert!(data.resources().is_empty());
+    assert_eq!(data.my_creeps()[0].capacity(), 0);
+  }
+
+  #[test]
+  fn rooms_before_v6() {
+    // rooms from v4 carry a controller, saved with the layout of the time
+    let old = (room_v3(), Some(controller_v6()));
+    let (data, _) = decode::<RoomData>(&saved(5, &old)).unwrap();
+    let controller = data.controller().unwrap();
+    assert_eq!(controller.level(), 2);
+    assert_eq!(controller.owner(), None);
+    assert!(data.resources().is_empty());
+
+    let current = RoomData::new(room(), 3);
+    let (data, _) = decode::<RoomData>(&saved(6, &current)).unwrap();
+    assert_eq!(data, current);
+  }
+
+  #[test]
+  fn actions_before_v5() {
+    let (action, _) = decode::<Action>(&saved(4, &12u32)).unwrap();
+    assert_eq!(action, Action::Transfer(Some(Energy), None));
+    let (action, _) = decode::<Action>(&saved(4, &13u32)).unwrap();
+    assert_eq!(action, Action::Withdraw(Some(Energy), None));
+    let (action, _) = decode::<Action>(&saved(4, &6u32)).unwrap();
+    assert_eq!(action, Action::Dismantle(false));
+  }
+
+  #[test]
+  fn actions_before_v8() {
+    let withdraw = (13u32, Some(Ghodium), Some(1_000u32));
+    let (action, _) = decode::<Action>(&saved(7, &withdraw)).unwrap();
+    assert_eq!(action, Action::Withdraw(Some(Ghodium), Some(1_000)));
+    let (action, _) = decode::<Action>(&saved(7, &6u32)).unwrap();
+    assert_eq!(action, Action::Dismantle(false));
+
+    let (action, _) =
+      decode::<Action>(&saved(8, &Action::Dismantle(true))).unwrap();
+    assert_eq!(action, Action::Dismantle(true));
+  }
+
+  #[test]
+  fn controllers_before_v7() {
+    let data = saved(6, &controller_v6());
+    let (controller, _) = decode::<ControllerData>(&data).unwrap();
+    assert_eq!(controller.progress(), 100);
+    assert_eq!(controller.ticks_to_downgrade(), 5_000);
+    assert_eq!(controller.owner(), None);
+    assert_eq!(controller.reserved_by(), None);
+  }
+
+  #[test]
+  fn controllers_before_v10() {
+    let data = saved(9, &controller_v9());
+    let (controller, _) = decode::<ControllerData>(&data).unwrap();
+    assert_eq!(controller.owner(), Some("Player"));
+    assert_eq!(controller.safe_mode_available(), 0);
+  }
+
+  #[test]
+  fn controllers_before_v11() {
+    let data = saved(10, &controller_v10());
+    let (controller, _) = decode::<ControllerData>(&data).unwrap();
+    assert_eq!(controller.owner(), Some("Player"));
+    assert_eq!(controller.safe_mode_available(), 3);
+    assert!(!controller.power_enabled());
+
+    let current = ControllerData::new(pos(20, 20), mock_id(2), true, 4);
+    let data = saved(11, &current);
+    assert_eq!(decode::<ControllerData>(&data).unwrap().0, current);
+  }
+
+  #[test]
+  fn directors_before_v9() {
+    let mut rooms = HashMap::new();
+    rooms.insert(room(), RoomData::new(room(), 1));
+    let scouted = HashMap::<RoomName, ()>::new();
+    let old = ("Player".to_string(), rooms, scouted, Vec::<()>::new());
+    let director = Director::from_saved(&saved(8, &old)).unwrap();
+    assert_eq!(director.owned_rooms()[&room()].level(), 1);
+    assert!(director.scouted_paths().is_empty());
+  }
+
+  #[test]
+  fn directors_before_v12() {
+    let mut paths = HashMap::new();
+    paths.insert((pos(1, 1), pos(3, 3)), vec![pos(2, 2)]);
+    let mut rooms = HashMap::new();
+    rooms.insert(room(), RoomData::new(room(), 1));
+    let old = (
+      "Player".to_string(),
+      HashMap::<RoomName, ()>::new(),
+      rooms,
+      Vec::<()>::new(),
+      paths.clone(),
+    );
+    let director = Director::from_saved(&saved(11, &old)).unwrap();
+    assert!(director.room(room()).is_some());
+    assert_eq!(director.scouted_paths(), &paths);
+
+    // rooms are saved in their own segments from v12 on
+    let current = ("Player".to_string(), Vec::<()>::new(), paths.clone());
+    let director = Director::from_saved(&saved(12, &current)).unwrap();
+    assert!(director.room(room()).is_none());
+    assert_eq!(director.scouted_paths(), &paths);
+    assert!(director.failures().is_empty());
+  }
+
+  #[test]
+  fn short_payloads_have_no_header() {
+    assert_eq!(split_header(&[]), (0, &[][..]));
+    assert_eq!(split_header(b"SDIR"), (0, &b"SDIR"[..]));
+    assert_eq!(
+      split_header(b"SDIR\x01\x00\x00"),
+      (0, &b"SDIR\x01\x00\x00"[..])
+    );
+  }
+
+  #[test]
+  fn future_versions_are_unsupported() {
+    let data = saved(SCHEMA_VERSION + 1, &());
+    match Director::from_saved(&data) {
+      Err(SchemaError::Unsupported(v)) => assert_eq!(v, SCHEMA_VERSION + 1),
+      other => panic!("expected an unsupported version, got {:?}", other.err()),
+    }
+  }
+}
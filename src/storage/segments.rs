@@ -0,0 +1,203 @@
+//! Stores large payloads across RawMemory segments.
+//!
+//! Each segment holds a header of `generation:index:count:` followed by a slice
+//! of the packed payload. The generation is the tick the payload was saved on
+//! so that a half written save is never stitched together with an older one.
+//!
+//! The segments are split into slots so that more than one payload can be kept.
+//! Only reading needs a segment to be active. The game saves at most 10
+//! segments a tick, counting the active ones, so segments are only kept active
+//! while they're being read.
+
+use crate::*;
+
+/// The first RawMemory segment used for the Director
+pub const FIRST_SEGMENT: u32 = 0;
+/// The most segments the Director may use.
+/// The game only allows 10 segments to be active at once.
+pub const MAX_SEGMENTS: u32 = 10;
+/// How many payloads can be stored side by side
+pub const SLOTS: u32 = 2;
+/// The most segments a single payload may span
+pub const SLOT_SEGMENTS: u32 = MAX_SEGMENTS / SLOTS;
+/// The most characters the game allows in a single segment
+pub const SEGMENT_SIZE: usize = 100 * 1024;
+
+/// Leaves room in each segment for the header
+const HEADER_ROOM: usize = 32;
+
+/// Anything that can go wrong reading or writing segments
+#[derive(Debug)]
+pub enum SegmentError {
+  /// These segments aren't active this tick. They have been requested for the next one.
+  Inactive(Vec<u32>),
+  /// Nothing has been saved yet
+  Empty,
+  /// A segment is missing or doesn't belong to the same save as the first one
+  Partial(u32),
+  /// A segment header couldn't be read
+  BadHeader(u32),
+  /// The packed payload couldn't be unpacked
+  Corrupt,
+  /// The checksum of the payload saved on this tick doesn't match its data
+  BadChecksum(u32),
+  /// The payload needs this many segments which is more than are available
+  TooLarge(usize, u32),
+}
+
+impl Display for SegmentError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    match self {
+      SegmentError::Inactive(ids) => {
+        write!(f, "segments {:?} aren't active yet", ids)
+      }
+      SegmentError::Empty => write!(f, "nothing has been saved"),
+      SegmentError::Partial(id) => {
+        write!(f, "segment {} is missing or from another save", id)
+      }
+      SegmentError::BadHeader(id) => {
+        write!(f, "segment {} has an unreadable header", id)
+      }
+      SegmentError::Corrupt => write!(f, "the saved data is corrupt"),
+      SegmentError::BadChecksum(generation) => {
+        write!(f, "the data saved on tick {} has a bad checksum", generation)
+      }
+      SegmentError::TooLarge(n, len) => write!(
+        f,
+        "the payload needs {} segments but only {} are available",
+        n, len
+      ),
+    }
+  }
+}
+
+impl std::error::Error for SegmentError {}
+
+/// A single parsed segment header
+struct Chunk<'a> {
+  generation: u32,
+  index: u32,
+  count: u32,
+  body: &'a str,
+}
+
+impl<'a> Chunk<'a> {
+  fn parse(text: &'a str) -> Option<Self> {
+    let mut parts = text.splitn(4, ':');
+    let generation = parts.next()?.parse().ok()?;
+    let index = parts.next()?.parse().ok()?;
+    let count = parts.next()?.parse().ok()?;
+    let body = parts.next()?;
+    Some(Chunk { generation, index, count, body })
+  }
+}
+
+/// All the segment ids the Director may use
+pub fn segment_ids() -> Vec<u32> {
+  (FIRST_SEGMENT..FIRST_SEGMENT + MAX_SEGMENTS).collect()
+}
+
+/// Ask the game to keep the Director's segments active
+pub fn request_segments(world: &mut dyn World) {
+  world.set_active_segments(&segment_ids());
+}
+
+/// The first segment of a slot
+pub fn slot_start(slot: u32) -> u32 {
+  FIRST_SEGMENT + slot * SLOT_SEGMENTS
+}
+
+/// Read a payload back out of a slot.
+/// Returns the generation it was saved with along with the payload.
+pub fn load_segments(
+  world: &mut dyn World,
+  slot: u32,
+) -> std::result::Result<(u32, Vec<u8>), SegmentError> {
+  let start = slot_start(slot);
+  let active = world.active_segments();
+  let inactive: Vec<u32> =
+    (start..start + SLOT_SEGMENTS).filter(|id| !active.contains(id)).collect();
+  if !inactive.is_empty() {
+    request_segments(world);
+    return Err(SegmentError::Inactive(inactive));
+  }
+
+  load_range(world, start, SLOT_SEGMENTS)
+}
+
+/// Write a payload across the segments of a slot.
+/// Segments in the slot that are no longer needed are cleared.
+pub fn save_segments(
+  world: &mut dyn World,
+  slot: u32,
+  data: &[u8],
+  generation: u32,
+) -> std::result::Result<(), SegmentError> {
+  save_range(world, slot_start(slot), SLOT_SEGMENTS, data, generation)
+}
+
+/// Read a payload back out of up to `len` segments starting at `start`,
+/// which must all be active.
+/// Returns the generation it was saved with along with the payload.
+pub fn load_range(
+  world: &dyn World,
+  start: u32,
+  len: u32,
+) -> std::result::Result<(u32, Vec<u8>), SegmentError> {
+  let first = world.segment(start).unwrap_or_default();
+  if first.is_empty() {
+    return Err(SegmentError::Empty);
+  }
+  let first = Chunk::parse(&first).ok_or(SegmentError::BadHeader(start))?;
+  if first.index != 0 || first.count > len {
+    return Err(SegmentError::BadHeader(start));
+  }
+
+  let mut text = String::from(first.body);
+  for index in 1..first.count {
+    let id = start + index;
+    let segment = world.segment(id).unwrap_or_default();
+    match Chunk::parse(&segment) {
+      Some(chunk)
+        if chunk.generation == first.generation
+          && chunk.index == index
+          && chunk.count == first.count =>
+      {
+        text.push_str(chunk.body)
+      }
+      _ => return Err(SegmentError::Partial(id)),
+    }
+  }
+
+  let data = decode_bytes(&text).ok_or(SegmentError::Corrupt)?;
+  Ok((first.generation, data))
+}
+
+/// Write a payload across up to `len` segments starting at `start`.
+/// Segments in the range that are no longer needed are cleared.
+pub fn save_range(
+  world: &mut dyn World,
+  start: u32,
+  len: u32,
+  data: &[u8],
+  generation: u32,
+) -> std::result::Result<(), SegmentError> {
+  let text: Vec<char> = encode_bytes(data).chars().collect();
+  let chunks: Vec<&[char]> = text.chunks(SEGMENT_SIZE - HEADER_ROOM).collect();
+  if chunks.len() > len as usize {
+    return Err(SegmentError::TooLarge(chunks.len(), len));
+  }
+
+  let count = chunks.len();
+  for index in 0..len {
+    let id = start + index;
+    if let Some(chunk) = chunks.get(index as usize) {
+      let mut segment = format!("{}:{}:{}:", generation, index, count);
+      segment.extend(chunk.iter());
+      world.set_segment(id, &segment);
+    } else {
+      world.set_segment(id, "");
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,177 @@
+//! Checksummed snapshots with a last-good backup.
+//!
+//! Every snapshot starts with a checksum of the rest of its bytes. Saves
+//! alternate between the segment slots so the previous snapshot is always kept
+//! around as a backup in case the newest one turns out to be bad.
+
+use crate::*;
+use std::cell::Cell;
+
+/// The size of the checksum in bytes
+const CHECKSUM_LEN: usize = 4;
+
+thread_local! {
+  /// The slot holding the snapshot we're running from.
+  /// New snapshots are written to the other slot.
+  static CURRENT_SLOT: Cell<u32> = Cell::new(0);
+}
+
+/// A 32 bit FNV-1a hash of the data
+pub fn checksum(data: &[u8]) -> u32 {
+  data.iter().fold(0x811c_9dc5, |hash: u32, byte| {
+    (hash ^ *byte as u32).wrapping_mul(0x0100_0193)
+  })
+}
+
+/// Prepend a checksum to the data
+pub fn seal(data: &[u8]) -> Vec<u8> {
+  let mut sealed = Vec::with_capacity(data.len() + CHECKSUM_LEN);
+  sealed.extend_from_slice(&checksum(data).to_le_bytes());
+  sealed.extend_from_slice(data);
+  sealed
+}
+
+/// Check and strip the checksum from sealed data
+pub fn unseal(sealed: &[u8]) -> Option<&[u8]> {
+  if sealed.len() < CHECKSUM_LEN {
+    return None;
+  }
+  let (sum, data) = sealed.split_at(CHECKSUM_LEN);
+  let mut expected = [0; CHECKSUM_LEN];
+  expected.copy_from_slice(sum);
+  if u32::from_le_bytes(expected) == checksum(data) {
+    Some(data)
+  } else {
+    None
+  }
+}
+
+/// A snapshot that was read back from a slot with a valid checksum
+pub struct Snapshot {
+  /// The slot the snapshot was read from
+  pub slot: u32,
+  /// The tick it was saved on
+  pub generation: u32,
+  /// The payload without its checksum
+  pub data: Vec<u8>,
+}
+
+impl Snapshot {
+  /// Name this snapshot for the logs
+  pub fn describe(&self) -> String {
+    format!("snapshot in slot {} from tick {}", self.slot, self.generation)
+  }
+
+  /// Remember this is the snapshot we're running from
+  /// so the next save doesn't overwrite it.
+  pub fn used(&self) {
+    CURRENT_SLOT.with(|s| s.set(self.slot));
+  }
+}
+
+/// Check the checksum of a payload read back from a slot.
+/// A mismatch is reported as `SegmentError::BadChecksum` so the payload is
+/// never decoded.
+pub fn check_snapshot(
+  slot: u32,
+  saved: std::result::Result<(u32, Vec<u8>), SegmentError>,
+) -> std::result::Result<Snapshot, SegmentError> {
+  let (generation, sealed) = saved?;
+  match unseal(&sealed) {
+    Some(data) => Ok(Snapshot { slot, generation, data: data.to_vec() }),
+    None => Err(SegmentError::BadChecksum(generation)),
+  }
+}
+
+/// Read the Director's snapshot in a slot
+pub fn read_snapshot(
+  world: &mut dyn World,
+  slot: u32,
+) -> std::result::Result<Snapshot, SegmentError> {
+  let saved = load_segments(world, slot);
+  check_snapshot(slot, saved)
+}
+
+/// Read every snapshot with a valid checksum, newest first.
+/// Slots that can't be read are logged along with the reason.
+/// Fails only when the segments aren't active yet.
+pub fn load_snapshots(
+  world: &mut dyn World,
+) -> std::result::Result<Vec<Snapshot>, SegmentError> {
+  let mut snapshots = vec![];
+  for slot in 0..SLOTS {
+    match read_snapshot(world, slot) {
+      Ok(snapshot) => snapshots.push(snapshot),
+      Err(SegmentError::Inactive(ids)) => {
+        return Err(SegmentError::Inactive(ids))
+      }
+      Err(SegmentError::Empty) => trace!("Slot {} is empty", slot),
+      Err(e) => warn!("Rejected the snapshot in slot {}: {}", slot, e),
+    }
+  }
+
+  snapshots.sort_by(|a, b| b.generation.cmp(&a.generation));
+  Ok(snapshots)
+}
+
+/// Save a new snapshot, keeping the one we're running from as the backup
+pub fn save_snapshot(
+  world: &mut dyn World,
+  data: &[u8],
+  generation: u32,
+) -> std::result::Result<(), SegmentError> {
+  let slot = (CURRENT_SLOT.with(|s| s.get()) + 1) % SLOTS;
+  save_segments(world, slot, &seal(data), generation)?;
+  CURRENT_SLOT.with(|s| s.set(slot));
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn world() -> MockWorld {
+    let mut world = MockWorld::new("Player");
+    world.active_segments = segment_ids();
+    world
+  }
+
+  #[test]
+  fn newest_snapshot_comes_first() {
+    let mut world = world();
+    save_snapshot(&mut world, b"old", 1).unwrap();
+    save_snapshot(&mut world, b"new", 2).unwrap();
+
+    let snapshots = load_snapshots(&mut world).unwrap();
+    let data: Vec<&[u8]> = snapshots.iter().map(|s| &s.data[..]).collect();
+    assert_eq!(data, vec![&b"new"[..], &b"old"[..]]);
+  }
+
+  #[test]
+  fn damaged_primary_falls_back_to_backup() {
+    let mut world = world();
+    save_snapshot(&mut world, b"backup", 1).unwrap();
+    save_snapshot(&mut world, b"primary", 2).unwrap();
+    let primary = CURRENT_SLOT.with(|s| s.get());
+    world.damage_segment(slot_start(primary));
+
+    let snapshots = load_snapshots(&mut world).unwrap();
+    assert_eq!(snapshots.len(), 1);
+    assert_eq!(snapshots[0].data, b"backup");
+    assert_eq!(snapshots[0].generation, 1);
+  }
+
+  #[test]
+  fn checksum_mismatch_is_reported() {
+    let mut world = world();
+    save_snapshot(&mut world, b"primary", 2).unwrap();
+    let slot = CURRENT_SLOT.with(|s| s.get());
+    world.damage_segment(slot_start(slot));
+
+    match read_snapshot(&mut world, slot) {
+      Err(SegmentError::BadChecksum(generation)) => assert_eq!(generation, 2),
+      Err(e) => panic!("expected a bad checksum, got {}", e),
+      Ok(snapshot) => panic!("decoded a damaged {}", snapshot.describe()),
+    }
+  }
+}
@@ -0,0 +1,59 @@
+use crate::*;
+
+/// Something a creep can be told to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum Intent {
+  /// Move toward a position
+  MoveTo(Position),
+  /// Harvest a Source, Mineral or Deposit
+  Harvest(Target),
+  /// Build a construction site
+  Build(Target),
+  /// Repair a structure
+  Repair(Target),
+  /// Dismantle a structure
+  Dismantle(Target),
+  /// Transfer a resource to a structure or creep.
+  /// Transfers everything of that resource when there is no amount.
+  Transfer(Target, ResourceType, Option<u32>),
+  /// Pick up a dropped resource
+  Pickup(Target),
+  /// Withdraw a resource from a structure, tombstone or ruin.
+  /// Withdraws as much as fits when there is no amount.
+  Withdraw(Target, ResourceType, Option<u32>),
+  /// Upgrade the controller in a room
+  UpgradeController(RoomName),
+  /// Claim a controller
+  Claim(Target),
+  /// Reserve a controller
+  Reserve(Target),
+  /// Attack the owner's or reserver's hold on a controller
+  AttackController(Target),
+  /// Hand ghodium to a controller for another safe mode
+  GenerateSafeMode(Target),
+  /// Attack a creep or structure next to us
+  Attack(Target),
+  /// Shoot a creep or structure up to 3 tiles away
+  RangedAttack(Target),
+  /// Hit everything hostile up to 3 tiles away, harder the closer it is
+  RangedMassAttack,
+  /// Heal a creep next to us
+  Heal(Target),
+  /// Heal a creep up to 3 tiles away
+  RangedHeal(Target),
+  /// Spawn a creep with this body at a spawn, named after the creep the
+  /// intent is for
+  SpawnCreep(Target, Vec<Part>),
+  /// Spawn a power creep at a power spawn
+  SpawnPowerCreep(Target),
+  /// Renew a power creep at a power spawn
+  Renew(Target),
+  /// Enable powers in the room of a controller
+  EnableRoom(Target),
+  /// Use a power, on a target if it needs one
+  UsePower(PowerType, Option<Target>),
+  /// Have a spawn recycle the creep
+  Recycle(Target),
+  /// Kill the creep on the spot
+  Suicide,
+}
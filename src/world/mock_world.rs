@@ -0,0 +1,298 @@
+use super::World;
+use crate::*;
+use screeps::Terrain;
+
+/// Make up an object id for a world that isn't the live game
+pub fn mock_id<T>(n: u32) -> ObjectId<T> {
+  format!("{:x}", n).parse().expect("hex is always a valid object id")
+}
+
+/// A room held by a `MockWorld`
+#[derive(Clone)]
+pub struct MockRoom {
+  /// Everything in the room
+  pub data: RoomData,
+  /// True if we own the controller
+  pub mine: bool,
+  /// The terrain of every tile indexed by `y * 50 + x`
+  pub terrain: Vec<Terrain>,
+}
+
+impl MockRoom {
+  /// A room with nothing but plains
+  pub fn new(data: RoomData, mine: bool) -> Self {
+    MockRoom { data, mine, terrain: vec![Terrain::Plain; 2500] }
+  }
+}
+
+/// An in-memory world for running the bot outside of the game.
+/// Every intent is recorded and answered with `reply`.
+pub struct MockWorld {
+  /// The current game tick
+  pub time: u32,
+  /// The CPU to report as used
+  pub cpu_used: f64,
+  /// The player's username
+  pub username: String,
+  /// Every visible room
+  pub rooms: HashMap<RoomName, MockRoom>,
+  /// The tasks saved in each creep's memory
+  pub creep_memory: HashMap<String, Task>,
+  /// The tick each idle creep finished its last task on
+  pub creep_idle: HashMap<String, u32>,
+  /// Arrays of bytes saved in Memory
+  pub memory: HashMap<String, Vec<u8>>,
+  /// Settings overrides
+  pub settings: HashMap<String, i32>,
+  /// Every RawMemory segment
+  pub segments: HashMap<u32, String>,
+  /// The segments that are active this tick
+  pub active_segments: Vec<u32>,
+  /// The segments requested for next tick
+  pub requested_segments: Vec<u32>,
+  /// Every power creep on the account
+  pub power_creeps: Vec<PowerCreepData>,
+  /// Every ruin in any room
+  pub ruins: Vec<RuinData>,
+  /// Every intent given so far
+  pub intents: Vec<(String, Intent)>,
+  /// Every room safe mode was activated in
+  pub safe_modes: Vec<RoomName>,
+  /// What every intent returns
+  pub reply: ReturnCode,
+}
+
+impl MockWorld {
+  /// An empty world
+  pub fn new(username: &str) -> Self {
+    MockWorld {
+      time: 0,
+      cpu_used: 0.0,
+      username: username.to_string(),
+      rooms: HashMap::new(),
+      creep_memory: HashMap::new(),
+      creep_idle: HashMap::new(),
+      memory: HashMap::new(),
+      settings: HashMap::new(),
+      segments: HashMap::new(),
+      active_segments: vec![],
+      requested_segments: vec![],
+      power_creeps: vec![],
+      ruins: vec![],
+      intents: vec![],
+      safe_modes: vec![],
+      reply: ReturnCode::Ok,
+    }
+  }
+
+  /// Add a room to the world
+  pub fn add_room(&mut self, room: MockRoom) {
+    self.rooms.insert(room.data.name(), room);
+  }
+
+  /// Move to the next tick, activating any requested segments
+  pub fn next_tick(&mut self) {
+    self.time += 1;
+    self.active_segments = self.requested_segments.clone();
+  }
+
+  /// Flip a byte in the middle of the payload held by a segment, leaving its
+  /// header alone
+  pub fn damage_segment(&mut self, id: u32) {
+    let segment = self.segments.get_mut(&id).expect("segment was never saved");
+    let header = segment.match_indices(':').nth(2).map_or(0, |(at, _)| at + 1);
+    let mut data = decode_bytes(&segment[header..]).expect("segment is packed");
+    let middle = data.len() / 2;
+    data[middle] ^= 0xff;
+    let damaged = format!("{}{}", &segment[..header], encode_bytes(&data));
+    *segment = damaged;
+  }
+
+  /// Find one of our creeps
+  pub fn creep_mut(&mut self, name: &str) -> Option<&mut CommonCreepData> {
+    self
+      .rooms
+      .values_mut()
+      .flat_map(|room| room.data.my_creeps_mut().iter_mut())
+      .find(|creep| creep.name() == name)
+  }
+}
+
+impl World for MockWorld {
+  fn time(&self) -> u32 {
+    self.time
+  }
+
+  fn cpu_used(&self) -> f64 {
+    self.cpu_used
+  }
+
+  fn username(&self) -> Option<String> {
+    Some(self.username.clone())
+  }
+
+  fn room_names(&self) -> Vec<RoomName> {
+    self.rooms.keys().cloned().collect()
+  }
+
+  fn room(&self, name: RoomName) -> Option<RoomData> {
+    self.rooms.get(&name).map(|room| room.data.clone())
+  }
+
+  fn is_mine(&self, name: RoomName) -> bool {
+    self.rooms.get(&name).map(|room| room.mine).unwrap_or(false)
+  }
+
+  fn terrain(&self, pos: Position) -> Terrain {
+    self
+      .rooms
+      .get(&pos.room_name())
+      .map(|room| room.terrain[pos.y() as usize * 50 + pos.x() as usize])
+      .unwrap_or(Terrain::Wall)
+  }
+
+  fn route(&self, from: RoomName, to: RoomName) -> Vec<RoomName> {
+    // every room is treated as if it were next door
+    if from == to {
+      vec![]
+    } else {
+      vec![to]
+    }
+  }
+
+  fn scout_path(&self, from: Position, to: Position) -> Vec<Position> {
+    if from.room_name() != to.room_name() {
+      return vec![to];
+    }
+
+    // a straight line that stops next to the goal, walls and all
+    let mut path = vec![];
+    let mut here = from;
+    while here.get_range_to(&to) > 1 {
+      let dx = (to.x() as i32 - here.x() as i32).signum();
+      let dy = (to.y() as i32 - here.y() as i32).signum();
+      here = Position::new(
+        (here.x() as i32 + dx) as u32,
+        (here.y() as i32 + dy) as u32,
+        here.room_name(),
+      );
+      path.push(here);
+    }
+    path
+  }
+
+  fn creep_names(&self) -> Vec<String> {
+    self
+      .rooms
+      .values()
+      .flat_map(|room| room.data.my_creeps().iter())
+      .map(|creep| creep.name().to_string())
+      .collect()
+  }
+
+  fn creep(&self, name: &str) -> Option<CommonCreepData> {
+    self
+      .rooms
+      .values()
+      .flat_map(|room| {
+        room.data.my_creeps().iter().chain(room.data.my_power_creeps())
+      })
+      .find(|creep| creep.name() == name)
+      .cloned()
+  }
+
+  fn my_creeps(&self) -> Vec<MyCreepData> {
+    let mut creeps: Vec<MyCreepData> = self
+      .rooms
+      .values()
+      .flat_map(|room| {
+        room.data.my_creeps().iter().chain(room.data.my_power_creeps())
+      })
+      .map(|creep| {
+        let task =
+          self.creep_memory.get(creep.name()).cloned().unwrap_or_default();
+        let idle_since = self.creep_idle.get(creep.name()).cloned();
+        MyCreepData::new(creep.clone(), task, idle_since)
+      })
+      .collect();
+    // keep the order stable so runs are repeatable
+    creeps.sort_by(|a, b| a.name().cmp(b.name()));
+    creeps
+  }
+
+  fn power_creeps(&self) -> Vec<PowerCreepData> {
+    self.power_creeps.clone()
+  }
+
+  fn refresh(&self, target: &Target) -> Option<Target> {
+    let room = self.rooms.get(&target.pos().room_name())?;
+    room.data.refresh(target)
+  }
+
+  fn ruins(&self, room: RoomName) -> Vec<RuinData> {
+    let ruins = self.ruins.iter().filter(|r| r.pos().room_name() == room);
+    ruins.cloned().collect()
+  }
+
+  fn creep_memory_names(&self) -> Option<Vec<String>> {
+    Some(self.creep_memory.keys().cloned().collect())
+  }
+
+  fn forget_creep(&mut self, name: &str) {
+    self.creep_memory.remove(name);
+    self.creep_idle.remove(name);
+  }
+
+  fn save_task(&mut self, name: &str, task: &Task) {
+    self.creep_memory.insert(name.to_string(), task.clone());
+  }
+
+  fn save_idle(&mut self, name: &str, since: Option<u32>) {
+    match since {
+      Some(since) => self.creep_idle.insert(name.to_string(), since),
+      None => self.creep_idle.remove(name),
+    };
+  }
+
+  fn memory_bytes(&self, key: &str) -> Option<Vec<u8>> {
+    self.memory.get(key).cloned()
+  }
+
+  fn delete_memory(&mut self, key: &str) {
+    self.memory.remove(key);
+  }
+
+  fn setting(&self, name: &str) -> Option<i32> {
+    self.settings.get(name).cloned()
+  }
+
+  fn active_segments(&self) -> Vec<u32> {
+    self.active_segments.clone()
+  }
+
+  fn set_active_segments(&mut self, ids: &[u32]) {
+    self.requested_segments = ids.to_vec();
+  }
+
+  fn segment(&self, id: u32) -> Option<String> {
+    if self.active_segments.contains(&id) {
+      Some(self.segments.get(&id).cloned().unwrap_or_default())
+    } else {
+      None
+    }
+  }
+
+  fn set_segment(&mut self, id: u32, data: &str) {
+    self.segments.insert(id, data.to_string());
+  }
+
+  fn intent(&mut self, creep: &str, intent: Intent) -> ReturnCode {
+    self.intents.push((creep.to_string(), intent));
+    self.reply
+  }
+
+  fn activate_safe_mode(&mut self, room: RoomName) -> ReturnCode {
+    self.safe_modes.push(room);
+    self.reply
+  }
+}
@@ -0,0 +1,110 @@
+//! Everything the bot needs from the game, behind a trait.
+//!
+//! The live game is only reachable inside the wasm runtime so all of the bot
+//! logic talks to a `World` instead. `ScreepsWorld` forwards to the real game
+//! and `MockWorld` keeps everything in memory so the logic can run natively.
+//! `MockWorld` is only built for tests and with the `native` feature.
+
+pub use intent::*;
+#[cfg(any(test, feature = "native"))]
+pub use mock_world::*;
+pub use screeps_world::*;
+
+mod intent;
+#[cfg(any(test, feature = "native"))]
+mod mock_world;
+mod screeps_world;
+
+use crate::*;
+use screeps::Terrain;
+
+/// A view of the game world along with a way to act on it
+pub trait World {
+  /// The current game tick
+  fn time(&self) -> u32;
+
+  /// The CPU used so far this tick
+  fn cpu_used(&self) -> f64;
+
+  /// The player's username
+  fn username(&self) -> Option<String>;
+
+  /// The names of every visible room
+  fn room_names(&self) -> Vec<RoomName>;
+
+  /// A fresh snapshot of a visible room
+  fn room(&self, name: RoomName) -> Option<RoomData>;
+
+  /// Returns true if we own the controller in this room
+  fn is_mine(&self, name: RoomName) -> bool;
+
+  /// The terrain at a position
+  fn terrain(&self, pos: Position) -> Terrain;
+
+  /// The rooms to pass through on the way from one room to another, ending
+  /// with the destination. Empty if there's no way there.
+  fn route(&self, from: RoomName, to: RoomName) -> Vec<RoomName>;
+
+  /// The tiles a scout walks from one position to next to another, treating
+  /// swamps like plains. Doesn't include the start.
+  fn scout_path(&self, from: Position, to: Position) -> Vec<Position>;
+
+  /// The names of every creep we own
+  fn creep_names(&self) -> Vec<String>;
+
+  /// A fresh snapshot of one of our creeps or spawned power creeps
+  fn creep(&self, name: &str) -> Option<CommonCreepData>;
+
+  /// Every creep we own along with its task
+  fn my_creeps(&self) -> Vec<MyCreepData>;
+
+  /// Every power creep on the account, spawned or not
+  fn power_creeps(&self) -> Vec<PowerCreepData>;
+
+  /// The current version of a target or None if it's gone
+  fn refresh(&self, target: &Target) -> Option<Target>;
+
+  /// The ruins in a visible room. Rooms don't keep track of these.
+  fn ruins(&self, room: RoomName) -> Vec<RuinData>;
+
+  /// The names of every creep that has memory.
+  /// None if Memory.creeps isn't there.
+  fn creep_memory_names(&self) -> Option<Vec<String>>;
+
+  /// Delete the memory of a creep
+  fn forget_creep(&mut self, name: &str);
+
+  /// Save the task a creep or spawned power creep is carrying to its memory
+  fn save_task(&mut self, name: &str, task: &Task);
+
+  /// Save the tick a creep finished its last task on to its memory,
+  /// or clear it once the creep has a new one
+  fn save_idle(&mut self, name: &str, since: Option<u32>);
+
+  /// Read an array of bytes from Memory
+  fn memory_bytes(&self, key: &str) -> Option<Vec<u8>>;
+
+  /// Delete a key from Memory
+  fn delete_memory(&mut self, key: &str);
+
+  /// Read a setting override from Memory
+  fn setting(&self, name: &str) -> Option<i32>;
+
+  /// The RawMemory segments that are active this tick
+  fn active_segments(&self) -> Vec<u32>;
+
+  /// Ask for RawMemory segments to be active next tick
+  fn set_active_segments(&mut self, ids: &[u32]);
+
+  /// Read a RawMemory segment. None if it isn't active.
+  fn segment(&self, id: u32) -> Option<String>;
+
+  /// Write a RawMemory segment
+  fn set_segment(&mut self, id: u32, data: &str);
+
+  /// Have one of our creeps do something
+  fn intent(&mut self, creep: &str, intent: Intent) -> ReturnCode;
+
+  /// Activate safe mode on the controller in a room we own
+  fn activate_safe_mode(&mut self, room: RoomName) -> ReturnCode;
+}
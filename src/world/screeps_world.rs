@@ -0,0 +1,491 @@
+use super::World;
+use crate::*;
+use screeps::{raw_memory, Terrain};
+
+/// Where the settings overrides are kept in Memory
+const SETTINGS_KEY: &str = "settings";
+
+/// The live game
+pub struct ScreepsWorld;
+
+impl World for ScreepsWorld {
+  fn time(&self) -> u32 {
+    game::time()
+  }
+
+  fn cpu_used(&self) -> f64 {
+    game::cpu::get_used()
+  }
+
+  fn username(&self) -> Option<String> {
+    game::spawns::values().get(0).and_then(|s| s.owner_name())
+  }
+
+  fn room_names(&self) -> Vec<RoomName> {
+    game::rooms::keys()
+  }
+
+  fn room(&self, name: RoomName) -> Option<RoomData> {
+    game::rooms::get(name).map(RoomData::from)
+  }
+
+  fn is_mine(&self, name: RoomName) -> bool {
+    game::rooms::get(name)
+      .and_then(|room| room.controller())
+      .map(|ctrl| ctrl.my())
+      .unwrap_or(false)
+  }
+
+  fn terrain(&self, pos: Position) -> Terrain {
+    let data = RoomTerrain::constructor(pos.room_name());
+    data.get(pos.x(), pos.y())
+  }
+
+  fn route(&self, from: RoomName, to: RoomName) -> Vec<RoomName> {
+    game::map::find_route(from, to)
+      .map(|steps| steps.into_iter().map(|step| step.room).collect())
+      .unwrap_or_default()
+  }
+
+  fn scout_path(&self, from: Position, to: Position) -> Vec<Position> {
+    CommonData::from(from).scout_path_to(vec![CommonData::from(to)]).path()
+  }
+
+  fn creep_names(&self) -> Vec<String> {
+    game::creeps::keys()
+  }
+
+  fn creep(&self, name: &str) -> Option<CommonCreepData> {
+    game::creeps::get(name)
+      .map(CommonCreepData::from)
+      .or_else(|| live_power_creep(name).map(CommonCreepData::from))
+  }
+
+  fn my_creeps(&self) -> Vec<MyCreepData> {
+    let power_creeps = game::rooms::values()
+      .into_iter()
+      .flat_map(|room| room.find(find::MY_POWER_CREEPS))
+      .map(MyCreepData::from);
+    game::creeps::values()
+      .into_iter()
+      .map(MyCreepData::from)
+      .chain(power_creeps)
+      .collect()
+  }
+
+  fn power_creeps(&self) -> Vec<PowerCreepData> {
+    game::power_creeps::values().into_iter().map(PowerCreepData::from).collect()
+  }
+
+  fn refresh(&self, target: &Target) -> Option<Target> {
+    match target {
+      Target::Source(s) => game::get_object_typed(s.id())
+        .ok()
+        .flatten()
+        .map(|o| Target::Source(o.into())),
+      Target::Mineral(m) => game::get_object_typed(m.id())
+        .ok()
+        .flatten()
+        .map(|o| Target::Mineral(o.into())),
+      Target::Deposit(d) => game::get_object_typed(d.id())
+        .ok()
+        .flatten()
+        .map(|o| Target::Deposit(o.into())),
+      Target::ConstructionSite(c) => game::get_object_typed(c.id())
+        .ok()
+        .flatten()
+        .map(|o| Target::ConstructionSite(o.into())),
+      Target::Ruin(r) => game::get_object_typed(r.id())
+        .ok()
+        .flatten()
+        .map(|o| Target::Ruin(o.into())),
+      Target::Creep(c) => game::get_object_typed(c.id())
+        .ok()
+        .flatten()
+        .map(|o| Target::Creep(o.into())),
+      Target::Structure(s) => {
+        s.structure().map(|o| Target::Structure(o.into()))
+      }
+      Target::Tombstone(t) => t
+        .pos()
+        .find_in_range(find::TOMBSTONES, 0)
+        .pop()
+        .map(|o| Target::Tombstone(o.into())),
+      Target::Controller(c) => game::get_object_typed(c.id())
+        .ok()
+        .flatten()
+        .map(|o| Target::Controller(o.into())),
+      Target::Resource(r) => game::get_object_typed(r.id())
+        .ok()
+        .flatten()
+        .map(|o| Target::Resource(o.into())),
+      Target::Path(_) => Some(target.clone()),
+    }
+  }
+
+  fn ruins(&self, room: RoomName) -> Vec<RuinData> {
+    match game::rooms::get(room) {
+      Some(room) => {
+        room.find(find::RUINS).into_iter().map(RuinData::from).collect()
+      }
+      None => vec![],
+    }
+  }
+
+  fn creep_memory_names(&self) -> Option<Vec<String>> {
+    root().dict("creeps").ok().flatten().map(|creeps| creeps.keys())
+  }
+
+  fn forget_creep(&mut self, name: &str) {
+    if let Ok(Some(creeps)) = root().dict("creeps") {
+      creeps.del(name);
+    }
+  }
+
+  fn save_task(&mut self, name: &str, task: &Task) {
+    let text = match task.to_memory() {
+      Ok(text) => text,
+      Err(e) => {
+        error!("Unable to serialize the task of {}: {}", name, e);
+        return;
+      }
+    };
+    if let Some(memory) = live_memory(name) {
+      memory.set(TASK_KEY, text);
+    }
+  }
+
+  fn save_idle(&mut self, name: &str, since: Option<u32>) {
+    if let Some(memory) = live_memory(name) {
+      match since {
+        Some(since) => memory.set(IDLE_KEY, since),
+        None => memory.del(IDLE_KEY),
+      }
+    }
+  }
+
+  fn memory_bytes(&self, key: &str) -> Option<Vec<u8>> {
+    root().arr(key).ok().flatten()
+  }
+
+  fn delete_memory(&mut self, key: &str) {
+    root().del(key);
+  }
+
+  fn setting(&self, name: &str) -> Option<i32> {
+    root()
+      .dict(SETTINGS_KEY)
+      .ok()
+      .flatten()
+      .and_then(|settings| settings.i32(name).ok().flatten())
+  }
+
+  fn active_segments(&self) -> Vec<u32> {
+    raw_memory::get_active_segments()
+  }
+
+  fn set_active_segments(&mut self, ids: &[u32]) {
+    raw_memory::set_active_segments(ids);
+  }
+
+  fn segment(&self, id: u32) -> Option<String> {
+    raw_memory::get_segment(id)
+  }
+
+  fn set_segment(&mut self, id: u32, data: &str) {
+    raw_memory::set_segment(id, data);
+  }
+
+  fn activate_safe_mode(&mut self, room: RoomName) -> ReturnCode {
+    match game::rooms::get(room).and_then(|r| r.controller()) {
+      Some(ctrl) => ctrl.activate_safe_mode(),
+      None => ReturnCode::NotFound,
+    }
+  }
+
+  fn intent(&mut self, creep: &str, intent: Intent) -> ReturnCode {
+    // creeps being spawned don't exist yet
+    if let Intent::SpawnCreep(target, body) = &intent {
+      return match target {
+        Target::Structure(s) => match s.structure() {
+          Some(Structure::Spawn(spawn)) => spawn.spawn_creep(body, creep),
+          _ => ReturnCode::InvalidTarget,
+        },
+        _ => ReturnCode::InvalidTarget,
+      };
+    }
+
+    // power creeps that aren't spawned only exist on the account
+    if let Intent::SpawnPowerCreep(target) = &intent {
+      let power_creep = match game::power_creeps::get(creep) {
+        Some(power_creep) => power_creep,
+        None => return ReturnCode::NotFound,
+      };
+      return match target {
+        Target::Structure(s) => match s.structure() {
+          Some(Structure::PowerSpawn(spawn)) => power_creep.spawn(&spawn),
+          _ => ReturnCode::InvalidTarget,
+        },
+        _ => ReturnCode::InvalidTarget,
+      };
+    }
+
+    let creep = match game::creeps::get(creep) {
+      Some(creep) => creep,
+      None => {
+        return match live_power_creep(creep) {
+          Some(power_creep) => power_intent(&power_creep, intent),
+          None => ReturnCode::NotFound,
+        }
+      }
+    };
+
+    match intent {
+      Intent::MoveTo(pos) => creep.move_to(&pos),
+      Intent::Harvest(target) => match target.as_harvestable() {
+        Some(t) => creep.harvest(t.as_ref()),
+        None => ReturnCode::InvalidTarget,
+      },
+      Intent::Build(Target::ConstructionSite(c)) => {
+        match game::get_object_typed(c.id()).ok().flatten() {
+          Some(site) => creep.build(&site),
+          None => ReturnCode::InvalidTarget,
+        }
+      }
+      Intent::Build(_) => ReturnCode::InvalidTarget,
+      Intent::Repair(Target::Structure(s)) => match s.structure() {
+        Some(structure) => creep.repair(&structure),
+        None => ReturnCode::InvalidTarget,
+      },
+      Intent::Repair(_) => ReturnCode::InvalidTarget,
+      Intent::Dismantle(Target::Structure(s)) => match s.structure() {
+        Some(structure) => creep.dismantle(&structure),
+        None => ReturnCode::InvalidTarget,
+      },
+      Intent::Dismantle(_) => ReturnCode::InvalidTarget,
+      Intent::Transfer(target, r, amount) => {
+        transfer(&creep, target, r, amount)
+      }
+      Intent::Pickup(target) => pickup(&creep, target),
+      Intent::Withdraw(target, r, amount) => {
+        withdraw(&creep, target, r, amount)
+      }
+      Intent::UpgradeController(room) => {
+        match game::rooms::get(room).and_then(|r| r.controller()) {
+          Some(ctrl) => creep.upgrade_controller(&ctrl),
+          None => ReturnCode::InvalidTarget,
+        }
+      }
+      Intent::Claim(target) => match controller(&target) {
+        Some(ctrl) => creep.claim_controller(&ctrl),
+        None => ReturnCode::InvalidTarget,
+      },
+      Intent::Reserve(target) => match controller(&target) {
+        Some(ctrl) => creep.reserve_controller(&ctrl),
+        None => ReturnCode::InvalidTarget,
+      },
+      Intent::AttackController(target) => match controller(&target) {
+        Some(ctrl) => creep.attack_controller(&ctrl),
+        None => ReturnCode::InvalidTarget,
+      },
+      Intent::GenerateSafeMode(target) => match controller(&target) {
+        Some(ctrl) => creep.generate_safe_mode(&ctrl),
+        None => ReturnCode::InvalidTarget,
+      },
+      Intent::Attack(target) => match target {
+        Target::Creep(c) => match live_creep(&c) {
+          Some(other) => creep.attack(&other),
+          None => ReturnCode::InvalidTarget,
+        },
+        Target::Structure(s) => {
+          match s.structure().as_ref().and_then(|s| s.as_attackable()) {
+            Some(structure) => creep.attack(structure),
+            None => ReturnCode::InvalidTarget,
+          }
+        }
+        _ => ReturnCode::InvalidTarget,
+      },
+      Intent::RangedAttack(target) => match target {
+        Target::Creep(c) => match live_creep(&c) {
+          Some(other) => creep.ranged_attack(&other),
+          None => ReturnCode::InvalidTarget,
+        },
+        Target::Structure(s) => {
+          match s.structure().as_ref().and_then(|s| s.as_attackable()) {
+            Some(structure) => creep.ranged_attack(structure),
+            None => ReturnCode::InvalidTarget,
+          }
+        }
+        _ => ReturnCode::InvalidTarget,
+      },
+      Intent::RangedMassAttack => creep.ranged_mass_attack(),
+      Intent::Heal(Target::Creep(c)) => match live_creep(&c) {
+        Some(other) => creep.heal(&other),
+        None => ReturnCode::InvalidTarget,
+      },
+      Intent::RangedHeal(Target::Creep(c)) => match live_creep(&c) {
+        Some(other) => creep.ranged_heal(&other),
+        None => ReturnCode::InvalidTarget,
+      },
+      Intent::Heal(_) | Intent::RangedHeal(_) => ReturnCode::InvalidTarget,
+      Intent::Recycle(Target::Structure(s)) => match s.structure() {
+        Some(Structure::Spawn(spawn)) => spawn.recycle_creep(&creep),
+        _ => ReturnCode::InvalidTarget,
+      },
+      Intent::Recycle(_) => ReturnCode::InvalidTarget,
+      Intent::Suicide => creep.suicide(),
+      // spawns do this, which was handled above
+      Intent::SpawnCreep(..) => ReturnCode::InvalidArgs,
+      // only power creeps can do these
+      Intent::SpawnPowerCreep(_)
+      | Intent::Renew(_)
+      | Intent::EnableRoom(_)
+      | Intent::UsePower(..) => ReturnCode::InvalidArgs,
+    }
+  }
+}
+
+/// Have one of our spawned power creeps do something
+fn power_intent(creep: &PowerCreep, intent: Intent) -> ReturnCode {
+  match intent {
+    Intent::MoveTo(pos) => creep.move_to(&pos),
+    Intent::Renew(Target::Structure(s)) => match s.structure() {
+      Some(Structure::PowerSpawn(spawn)) => creep.renew(&spawn),
+      _ => ReturnCode::InvalidTarget,
+    },
+    Intent::Renew(_) => ReturnCode::InvalidTarget,
+    Intent::EnableRoom(target) => match controller(&target) {
+      Some(ctrl) => creep.enable_room(&ctrl),
+      None => ReturnCode::InvalidTarget,
+    },
+    Intent::UsePower(power, None) => creep.use_power::<Structure>(power, None),
+    Intent::UsePower(power, Some(Target::Structure(s))) => {
+      match s.structure() {
+        Some(structure) => creep.use_power(power, Some(&structure)),
+        None => ReturnCode::InvalidTarget,
+      }
+    }
+    Intent::UsePower(power, Some(Target::Source(s))) => {
+      match game::get_object_typed(s.id()).ok().flatten() {
+        Some(source) => creep.use_power(power, Some(&source)),
+        None => ReturnCode::InvalidTarget,
+      }
+    }
+    Intent::UsePower(..) => ReturnCode::InvalidTarget,
+    Intent::Transfer(target, r, amount) => transfer(creep, target, r, amount),
+    Intent::Pickup(target) => pickup(creep, target),
+    Intent::Withdraw(target, r, amount) => withdraw(creep, target, r, amount),
+    Intent::Suicide => creep.suicide(),
+    // power creeps have no body for anything else
+    _ => ReturnCode::NoBodypart,
+  }
+}
+
+/// Give a resource to a structure or creep
+fn transfer<C: SharedCreepProperties>(
+  creep: &C,
+  target: Target,
+  r: ResourceType,
+  amount: Option<u32>,
+) -> ReturnCode {
+  match target {
+    Target::Structure(s) => {
+      match s.structure().as_ref().and_then(|s| s.as_transferable()) {
+        Some(t) => match amount {
+          Some(amount) => creep.transfer_amount(t, r, amount),
+          None => creep.transfer_all(t, r),
+        },
+        None => ReturnCode::InvalidTarget,
+      }
+    }
+    Target::Creep(c) => match game::get_object_typed(c.id()).ok().flatten() {
+      Some(other) => match amount {
+        Some(amount) => creep.transfer_amount(&other, r, amount),
+        None => creep.transfer_all(&other, r),
+      },
+      None => ReturnCode::InvalidTarget,
+    },
+    _ => ReturnCode::InvalidTarget,
+  }
+}
+
+/// Pick up a dropped resource
+fn pickup<C: SharedCreepProperties>(creep: &C, target: Target) -> ReturnCode {
+  match target {
+    Target::Resource(r) => {
+      match game::get_object_typed(r.id()).ok().flatten() {
+        Some(resource) => creep.pickup(&resource),
+        None => ReturnCode::InvalidTarget,
+      }
+    }
+    _ => ReturnCode::InvalidTarget,
+  }
+}
+
+/// Take a resource out of a structure, tombstone or ruin
+fn withdraw<C: SharedCreepProperties>(
+  creep: &C,
+  target: Target,
+  r: ResourceType,
+  amount: Option<u32>,
+) -> ReturnCode {
+  match target {
+    Target::Structure(s) => {
+      match s.structure().as_ref().and_then(|s| s.as_withdrawable()) {
+        Some(t) => match amount {
+          Some(amount) => creep.withdraw_amount(t, r, amount),
+          None => creep.withdraw_all(t, r),
+        },
+        None => ReturnCode::InvalidTarget,
+      }
+    }
+    Target::Tombstone(t) => {
+      match t.pos().find_in_range(find::TOMBSTONES, 0).pop() {
+        Some(tombstone) => match amount {
+          Some(amount) => creep.withdraw_amount(&tombstone, r, amount),
+          None => creep.withdraw_all(&tombstone, r),
+        },
+        None => ReturnCode::InvalidTarget,
+      }
+    }
+    Target::Ruin(ruin) => {
+      match game::get_object_typed(ruin.id()).ok().flatten() {
+        Some(ruin) => match amount {
+          Some(amount) => creep.withdraw_amount(&ruin, r, amount),
+          None => creep.withdraw_all(&ruin, r),
+        },
+        None => ReturnCode::InvalidTarget,
+      }
+    }
+    _ => ReturnCode::InvalidTarget,
+  }
+}
+
+/// Look up one of our spawned power creeps by name
+fn live_power_creep(name: &str) -> Option<PowerCreep> {
+  game::rooms::values()
+    .into_iter()
+    .flat_map(|room| room.find(find::MY_POWER_CREEPS))
+    .find(|creep| creep.name() == name)
+}
+
+/// The memory of one of our creeps or spawned power creeps
+fn live_memory(name: &str) -> Option<MemoryReference> {
+  match game::creeps::get(name) {
+    Some(creep) => Some(creep.memory()),
+    None => live_power_creep(name).map(|creep| creep.memory()),
+  }
+}
+
+/// Look up the live controller for a target
+fn controller(target: &Target) -> Option<StructureController> {
+  match target {
+    Target::Controller(c) => game::get_object_typed(c.id()).ok().flatten(),
+    _ => None,
+  }
+}
+
+/// Look up the live creep for creep data
+fn live_creep(c: &CommonCreepData) -> Option<Creep> {
+  game::get_object_typed(c.id()).ok().flatten()
+}